@@ -0,0 +1,229 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::collections::HashSet;
+
+use goblin::{
+    archive::Archive,
+    elf::{
+        sym::{
+            Sym, STB_GLOBAL, STB_WEAK, STT_FUNC, STT_GNU_IFUNC, STT_OBJECT, STT_TLS, STV_DEFAULT,
+        },
+        Elf,
+    },
+};
+use serde::Serialize;
+
+/// An ObjectMember is a relocatable object, either standalone or held within
+/// a static archive, along with the symbols it needs from elsewhere.
+#[derive(Debug, Serialize, Clone)]
+pub struct ObjectMember {
+    name: String,
+    undefined_symbols: Vec<String>,
+}
+
+impl ObjectMember {
+    pub fn undefined_symbols(&self) -> &Vec<String> {
+        &self.undefined_symbols
+    }
+}
+
+fn is_global(sym: &Sym) -> bool {
+    matches!(sym.st_bind(), STB_GLOBAL | STB_WEAK)
+}
+
+/// Split the global symbols of a relocatable object into the ones it
+/// defines and the ones it leaves undefined.
+fn object_symbols(elf: &Elf) -> (Vec<String>, Vec<String>) {
+    let mut defined: Vec<String> = vec![];
+    let mut undefined: Vec<String> = vec![];
+
+    for sym in elf.syms.iter().filter(is_global) {
+        let Some(name) = elf.strtab.get_at(sym.st_name).filter(|n| !n.is_empty()) else {
+            continue;
+        };
+
+        if sym.st_shndx == 0 {
+            undefined.push(name.to_string());
+        } else {
+            defined.push(name.to_string());
+        }
+    }
+
+    (defined, undefined)
+}
+
+/// Create the member for a standalone relocatable object.
+pub fn object_member(name: String, elf: &Elf) -> ObjectMember {
+    let (_, mut undefined_symbols) = object_symbols(elf);
+    undefined_symbols.sort();
+    undefined_symbols.dedup();
+
+    ObjectMember {
+        name,
+        undefined_symbols,
+    }
+}
+
+/// Parse every ELF member of a static archive. Symbols that are satisfied by
+/// another member of the same archive are not reported as undefined, since
+/// the linker resolves them without looking any further.
+pub fn archive_members(archive: &Archive, data: &[u8]) -> Vec<ObjectMember> {
+    let mut parsed: Vec<(String, Vec<String>)> = vec![];
+    let mut defined: HashSet<String> = HashSet::new();
+
+    // Members are visited in archive order, so the report is stable.
+    let names = (0..archive.len()).filter_map(|i| archive.get_at(i).map(|m| m.extended_name()));
+    for name in names {
+        let Ok(bytes) = archive.extract(name, data) else {
+            continue;
+        };
+        let Ok(elf) = Elf::parse(bytes) else {
+            continue;
+        };
+
+        let (d, u) = object_symbols(&elf);
+        defined.extend(d);
+        parsed.push((name.to_string(), u));
+    }
+
+    parsed
+        .into_iter()
+        .map(|(name, undefined)| {
+            let mut undefined_symbols: Vec<String> = undefined
+                .into_iter()
+                .filter(|s| !defined.contains(s))
+                .collect();
+            undefined_symbols.sort();
+            undefined_symbols.dedup();
+
+            ObjectMember {
+                name,
+                undefined_symbols,
+            }
+        })
+        .collect()
+}
+
+/// A symbol made available by a shared library.
+#[derive(Debug, Clone)]
+pub struct ExportedSymbol {
+    pub name: String,
+
+    /// Weak definitions are commonly interposers, such as sanitizer runtimes,
+    /// so strong definitions of the same symbol take precedence.
+    pub weak: bool,
+}
+
+/// Collect the symbols a shared library makes available to other objects.
+pub fn exported_symbols(elf: &Elf) -> Vec<ExportedSymbol> {
+    elf.dynsyms
+        .iter()
+        .filter(|sym| {
+            sym.st_shndx != 0
+                && is_global(sym)
+                && sym.st_visibility() == STV_DEFAULT
+                && matches!(
+                    sym.st_type(),
+                    STT_FUNC | STT_OBJECT | STT_TLS | STT_GNU_IFUNC
+                )
+        })
+        .filter_map(|sym| {
+            let name = elf.dynstrtab.get_at(sym.st_name)?;
+            (!name.is_empty()).then(|| ExportedSymbol {
+                name: name.to_string(),
+                weak: sym.st_bind() == STB_WEAK,
+            })
+        })
+        .collect()
+}
+
+/// Collect the dynamic symbols an object expects another object to define.
+pub fn imported_symbols(elf: &Elf) -> Vec<String> {
+    elf.dynsyms
+        .iter()
+        .filter(|sym| sym.st_shndx == 0 && is_global(sym))
+        .filter_map(|sym| elf.dynstrtab.get_at(sym.st_name))
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBA: &[u8] = include_bytes!("../tests/fixtures/elf/liba.so");
+    const LIBB: &[u8] = include_bytes!("../tests/fixtures/elf/libb.so");
+    const STRONG: &[u8] = include_bytes!("../tests/fixtures/elf/strong.o");
+    const LIBSTATIC: &[u8] = include_bytes!("../tests/fixtures/elf/libstatic.a");
+
+    #[test]
+    fn exports_default_visibility_symbols() {
+        let elf = Elf::parse(LIBB).unwrap();
+        let mut exports: Vec<(String, bool)> = exported_symbols(&elf)
+            .into_iter()
+            .map(|e| (e.name, e.weak))
+            .collect();
+        exports.sort();
+
+        // The hidden symbol stays out, and the weak one is marked as such.
+        assert_eq!(
+            exports,
+            [("b_hook".to_string(), true), ("b_value".to_string(), false)]
+        );
+        assert!(imported_symbols(&elf).is_empty());
+    }
+
+    #[test]
+    fn imports_undefined_dynamic_symbols() {
+        let elf = Elf::parse(LIBA).unwrap();
+        assert_eq!(imported_symbols(&elf), ["b_value"]);
+
+        let exports: Vec<String> = exported_symbols(&elf).into_iter().map(|e| e.name).collect();
+        assert_eq!(exports, ["a_value"]);
+    }
+
+    #[test]
+    fn standalone_object_keeps_every_undefined_symbol() {
+        let elf = Elf::parse(STRONG).unwrap();
+        let member = object_member("strong.o".to_string(), &elf);
+        assert_eq!(member.undefined_symbols(), &["ext_fn", "helper_fn"]);
+
+        let (mut defined, _) = object_symbols(&elf);
+        defined.sort();
+        assert_eq!(defined, ["strong_fn", "weak_fn"]);
+    }
+
+    #[test]
+    fn archive_members_satisfy_each_other() {
+        let ar = Archive::parse(LIBSTATIC).unwrap();
+        let members: Vec<(String, Vec<String>)> = archive_members(&ar, LIBSTATIC)
+            .into_iter()
+            .map(|m| (m.name, m.undefined_symbols))
+            .collect();
+
+        assert_eq!(
+            members,
+            [
+                ("strong.o".to_string(), vec!["ext_fn".to_string()]),
+                ("helper.o".to_string(), vec!["other_ext".to_string()]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{collections::VecDeque, time::Instant};
+
+use rand::{rngs::StdRng, seq::index::sample, SeedableRng};
+use rayon::prelude::*;
+use rustworkx_core::petgraph::graph::{DiGraph, NodeIndex};
+
+/// Number of sources timed to estimate the cost of the exact computation.
+const PILOT_SOURCES: usize = 16;
+
+/// Accumulate the dependency of a single source on every other node, as in
+/// Brandes' algorithm over unweighted shortest paths.
+fn accumulate<N, E>(graph: &DiGraph<N, E>, s: usize, endpoints: bool, out: &mut [f64]) {
+    let n = graph.node_count();
+    let mut sigma = vec![0.; n];
+    let mut dist: Vec<Option<usize>> = vec![None; n];
+    let mut preds: Vec<Vec<usize>> = vec![vec![]; n];
+    let mut order: Vec<usize> = vec![];
+    let mut queue: VecDeque<usize> = VecDeque::from([s]);
+
+    sigma[s] = 1.;
+    dist[s] = Some(0);
+    while let Some(v) = queue.pop_front() {
+        order.push(v);
+        let d = dist[v].unwrap_or_default();
+        for w in graph.neighbors(NodeIndex::new(v)).map(|w| w.index()) {
+            if dist[w].is_none() {
+                dist[w] = Some(d + 1);
+                queue.push_back(w);
+            }
+            if dist[w] == Some(d + 1) {
+                sigma[w] += sigma[v];
+                preds[w].push(v);
+            }
+        }
+    }
+
+    let mut delta = vec![0.; n];
+    for w in order.iter().rev() {
+        let coeff = (1. + delta[*w]) / sigma[*w];
+        for v in preds[*w].iter() {
+            delta[*v] += sigma[*v] * coeff;
+        }
+    }
+
+    if endpoints {
+        out[s] += (order.len() - 1) as f64;
+    }
+    for w in order.iter().filter(|w| **w != s) {
+        out[*w] += delta[*w] + if endpoints { 1. } else { 0. };
+    }
+}
+
+/// Sum the dependencies of every given source, in parallel.
+fn from_sources<N: Sync, E: Sync>(
+    graph: &DiGraph<N, E>,
+    sources: &[usize],
+    endpoints: bool,
+) -> Vec<f64> {
+    let n = graph.node_count();
+    sources
+        .par_iter()
+        .fold(
+            || vec![0.; n],
+            |mut acc, s| {
+                accumulate(graph, *s, endpoints, &mut acc);
+                acc
+            },
+        )
+        .reduce(
+            || vec![0.; n],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+                a
+            },
+        )
+}
+
+/// Estimate betweenness from a uniform sample of source nodes, scaling the
+/// sum over the sample up to every node. Scaling matches the exact
+/// computation, so the two are interchangeable.
+pub fn sampled<N: Sync, E: Sync>(
+    graph: &DiGraph<N, E>,
+    samples: usize,
+    seed: u64,
+    endpoints: bool,
+    normalized: bool,
+) -> Vec<Option<f64>> {
+    let n = graph.node_count();
+    let k = samples.max(1).min(n);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let sources = sample(&mut rng, n, k).into_vec();
+
+    let mut scale = if k > 0 { n as f64 / k as f64 } else { 0. };
+    if normalized {
+        let pairs = if endpoints {
+            n * n.saturating_sub(1)
+        } else {
+            n.saturating_sub(1) * n.saturating_sub(2)
+        };
+        if pairs > 0 {
+            scale /= pairs as f64;
+        }
+    }
+
+    from_sources(graph, &sources, endpoints)
+        .into_iter()
+        .map(|b| Some(b * scale))
+        .collect()
+}
+
+/// Estimate how long the exact computation would take, in seconds, by timing
+/// a handful of sources.
+pub fn estimate_exact_seconds<N: Sync, E: Sync>(graph: &DiGraph<N, E>, seed: u64) -> f64 {
+    let n = graph.node_count();
+    let k = PILOT_SOURCES.min(n);
+    if k == 0 {
+        return 0.;
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let sources = sample(&mut rng, n, k).into_vec();
+
+    let start = Instant::now();
+    for s in sources {
+        let mut out = vec![0.; n];
+        accumulate(graph, s, true, &mut out);
+    }
+    start.elapsed().as_secs_f64() / k as f64 * n as f64 / rayon::current_num_threads() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustworkx_core::centrality::betweenness_centrality;
+
+    fn assert_close(a: &[Option<f64>], b: &[Option<f64>]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x.unwrap() - y.unwrap()).abs() < 1e-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn sampling_every_source_matches_exact() {
+        // A diamond feeding a path, so shortest paths split and merge.
+        let graph: DiGraph<(), ()> =
+            DiGraph::from_edges([(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (4, 5)]);
+        let n = graph.node_count();
+
+        for endpoints in [false, true] {
+            for normalized in [false, true] {
+                let exact = betweenness_centrality(&graph, endpoints, normalized, 4);
+                let estimate = sampled(&graph, n, 7, endpoints, normalized);
+                assert_close(&estimate, &exact);
+            }
+        }
+    }
+
+    #[test]
+    fn scales_partial_samples_to_every_source() {
+        // Every source of a directed cycle contributes the same total, so a
+        // partial sample scaled up keeps the exact total.
+        let graph: DiGraph<(), ()> =
+            DiGraph::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 0)]);
+        let total = |values: Vec<Option<f64>>| values.iter().flatten().sum::<f64>();
+
+        for normalized in [false, true] {
+            let exact = total(betweenness_centrality(&graph, false, normalized, 4));
+            let estimate = total(sampled(&graph, 2, 3, false, normalized));
+            assert!((estimate - exact).abs() < 1e-9, "{} != {}", estimate, exact);
+        }
+    }
+
+    #[test]
+    fn samples_are_seeded() {
+        let graph: DiGraph<(), ()> =
+            DiGraph::from_edges([(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (1, 4)]);
+        assert_eq!(
+            sampled(&graph, 2, 42, true, true),
+            sampled(&graph, 2, 42, true, true)
+        );
+    }
+}
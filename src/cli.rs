@@ -18,21 +18,189 @@
 
 use std::env;
 
+use crate::metrics::MetricSpec;
+
 #[derive(Debug, clap::Parser)]
 pub struct BingraphArgs {
+    /// Alternative graph to build instead of the binary search path graph.
+    #[command(subcommand)]
+    pub command: Option<BingraphCommand>,
+
     /// Output location for constructed graph JSON.
-    #[arg(short, long, default_value_t = String::from("graph.json"))]
+    #[arg(short, long, global = true, default_value_t = String::from("graph.json"))]
     pub output: String,
 
     /// Output location for constructed graphviz.
-    #[arg(long, default_value_t = String::from(""))]
+    #[arg(long, global = true, default_value_t = String::from(""))]
     pub output_graphviz: String,
 
+    /// Output location for the condensation JSON, the acyclic graph of the
+    /// strongly connected components.
+    #[arg(long, global = true, default_value_t = String::from(""))]
+    pub output_condensation: String,
+
+    /// Root directory of the system being scanned, under which package
+    /// databases are read to find the package owning each node. The
+    /// KnownDLLs and API sets of a Windows installation or Wine prefix found
+    /// there replace the built-in defaults.
+    #[arg(long, global = true, default_value_t = String::from("/"))]
+    pub root: String,
+
+    /// Include the systemd services found under the root, linked to the
+    /// binaries they execute.
+    #[arg(long, global = true, default_value_t = false)]
+    pub services: bool,
+
+    /// Metrics to compute for every node, separated by commas. Each may be
+    /// followed by its parameters, as in `katz:alpha=0.2:max-iter=500`. The
+    /// metrics are betweenness (normalized, endpoints, threads, samples, seed,
+    /// budget in seconds), katz (alpha, beta, tolerance, max-iter),
+    /// eigenvector (tolerance, max-iter), closeness (normalized, threads),
+    /// pagerank (alpha, tolerance, max-iter), hits (tolerance, max-iter),
+    /// kcore, community (resolution, seed) and closure, the transitive
+    /// dependency measures.
+    #[arg(
+        long,
+        global = true,
+        value_delimiter = ',',
+        default_value = "betweenness,katz,eigenvector,closeness"
+    )]
+    pub metrics: Vec<MetricSpec>,
+
     /// Specify a path string to search through for acquiring binaries.
-    #[arg(short, long, default_value_t = env::var("PATH").unwrap().to_string())]
+    #[arg(short, long, global = true, default_value_t = env::var("PATH").unwrap().to_string())]
     pub bin_path: String,
 
     /// Specify a path string to search through for acquiring binaries.
-    #[arg(short, long, default_value_t = String::from("/usr/x86_64-pc-linux-gnu/lib64:/usr/x86_64-linux-gnu/lib64:/usr/lib:/usr/local/lib:/usr/x86_64-pc-linux-gnu/lib:/usr/x86_64-linux-gnu/lib:/usr/local/lib/x86_64-linux-gnu:/lib/x86_64-linux-gnu:/usr/lib/x86_64-linux-gnu:/usr/lib/x86_64-linux-gnu64:/lib:/lib64:/usr/local/lib64:"))]
+    #[arg(short, long, global = true, default_value_t = String::from("/usr/x86_64-pc-linux-gnu/lib64:/usr/x86_64-linux-gnu/lib64:/usr/lib:/usr/local/lib:/usr/x86_64-pc-linux-gnu/lib:/usr/x86_64-linux-gnu/lib:/usr/local/lib/x86_64-linux-gnu:/lib/x86_64-linux-gnu:/usr/lib/x86_64-linux-gnu:/usr/lib/x86_64-linux-gnu64:/lib:/lib64:/usr/local/lib64:"))]
     pub lib_path: String,
 }
+
+#[derive(Debug, clap::Subcommand)]
+pub enum BingraphCommand {
+    /// Build a dependency graph of the kernel modules installed for a kernel release.
+    Kmod(KmodArgs),
+
+    /// Build a dependency graph of the Python extension modules in site-packages.
+    Python(PythonArgs),
+
+    /// Collapse the binary graph into a graph of the packages owning its
+    /// files, checking it against their declared dependencies.
+    Packages,
+
+    /// Build a runtime graph of the running processes and the files they map.
+    Live(LiveArgs),
+
+    /// Report the processes, grouped by systemd unit, that still run
+    /// libraries or executables that were since upgraded or deleted.
+    Restart(RestartArgs),
+
+    /// Overlay the library loads recorded in `LD_DEBUG=libs,files` logs onto
+    /// the graph of the binary and library paths.
+    LdDebug(LdDebugArgs),
+
+    /// List everything that would lose a dependency if the given libraries
+    /// were removed or replaced. The graphviz output holds the affected subgraph.
+    Impact(ImpactArgs),
+
+    /// Explain why one node depends upon another, listing the edges along
+    /// the shortest path and the symbols behind each of them.
+    Why(WhyArgs),
+
+    /// Print the dependency tree of a single file as the resolver sees it,
+    /// without running the loader.
+    Tree(TreeArgs),
+
+    /// Simulate the order in which the dynamic loader maps each executable's
+    /// libraries and runs their constructors.
+    InitOrder(InitOrderArgs),
+}
+
+#[derive(Debug, clap::Args)]
+pub struct KmodArgs {
+    /// Kernel release to scan modules for, defaults to the running kernel.
+    #[arg(short, long)]
+    pub release: Option<String>,
+
+    /// Directory containing the per-release module directories.
+    #[arg(long, default_value_t = String::from("/lib/modules"))]
+    pub modules_dir: String,
+
+    /// Cross-check the modinfo dependencies against modules.dep.
+    #[arg(long, default_value_t = false)]
+    pub check: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct PythonArgs {
+    /// Path string of site-packages directories to scan, defaults to those of
+    /// every Python 3 installation found.
+    #[arg(short, long)]
+    pub site_packages: Option<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct LiveArgs {
+    /// Directory the process filesystem is mounted on.
+    #[arg(long, default_value_t = String::from("/proc"))]
+    pub proc_dir: String,
+
+    /// Merge with the static graph of the binary and library paths, marking
+    /// the nodes and edges in use.
+    #[arg(long, default_value_t = false)]
+    pub merge: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct RestartArgs {
+    /// Directory the process filesystem is mounted on.
+    #[arg(long, default_value_t = String::from("/proc"))]
+    pub proc_dir: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct LdDebugArgs {
+    /// Log files written by the dynamic loader with `LD_DEBUG=libs,files`.
+    #[arg(required = true)]
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ImpactArgs {
+    /// Names or paths of the libraries to analyze.
+    #[arg(required = true)]
+    pub libraries: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct WhyArgs {
+    /// Name or path of the dependent node.
+    pub from: String,
+
+    /// Name or path of the dependency.
+    pub to: String,
+
+    /// Also list every simple path, not just the shortest.
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+
+    /// Longest path to follow when listing every path.
+    #[arg(long, default_value_t = 8)]
+    pub max_depth: usize,
+
+    /// Stop listing paths after this many have been found.
+    #[arg(long, default_value_t = 100)]
+    pub max_paths: usize,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct TreeArgs {
+    /// Path of the file to resolve.
+    pub path: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct InitOrderArgs {
+    /// Names or paths of the executables to simulate, defaults to all of them.
+    pub executables: Vec<String>,
+}
@@ -0,0 +1,209 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use rustworkx_core::petgraph::graph::DiGraph;
+
+use crate::{node::BinNode, scc::Components};
+
+/// A fixed size set of node indices.
+#[derive(Clone)]
+struct BitSet(Vec<u64>);
+
+impl BitSet {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn union(&mut self, other: &BitSet) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a |= b;
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(w, bits)| {
+            let mut bits = *bits;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let b = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(w * 64 + b)
+            })
+        })
+    }
+}
+
+/// Closure holds the per-node transitive dependency measures of a graph.
+pub struct Closure {
+    pub dependencies: Vec<u32>,
+    pub size: Vec<u64>,
+    pub depth: Vec<u32>,
+
+    /// Executables transitively depending on each node, only for libraries.
+    pub dependent_executables: Vec<Option<u32>>,
+}
+
+impl Closure {
+    /// Compute the transitive dependency measures of every node at once.
+    /// Strongly connected components are collapsed first, so that each set
+    /// is built from the sets of its successors in a single reverse
+    /// topological pass. Members of a cycle all share the same set, and the
+    /// cycle counts as a single step of depth. Only the measures are kept:
+    /// a set is dropped as soon as every component depending on it is done,
+    /// so few sets are alive at once.
+    pub fn compute<E>(nodes: &[BinNode], graph: &DiGraph<(), E>, components: &Components) -> Self {
+        println!("computing transitive closure for graph");
+        let n = graph.node_count();
+
+        // Components are in reverse topological order, so the successors of a
+        // component are always complete before it.
+        let sccs = &components.members;
+        let component = &components.component;
+
+        let succs: Vec<Vec<usize>> = sccs
+            .iter()
+            .enumerate()
+            .map(|(c, members)| {
+                let mut succs: Vec<usize> = members
+                    .iter()
+                    .flat_map(|idx| graph.neighbors(*idx))
+                    .map(|succ| component[succ.index()])
+                    .filter(|sc| *sc != c)
+                    .collect();
+                succs.sort_unstable();
+                succs.dedup();
+                succs
+            })
+            .collect();
+
+        // The number of components still to consume the set of each one.
+        let mut waiting = vec![0; sccs.len()];
+        for sc in succs.iter().flatten() {
+            waiting[*sc] += 1;
+        }
+
+        let mut sets: Vec<Option<BitSet>> = vec![None; sccs.len()];
+        let mut depths: Vec<u32> = vec![0; sccs.len()];
+
+        let mut dependencies = vec![0; n];
+        let mut size = vec![0; n];
+        let mut depth = vec![0; n];
+        let mut dependents = vec![0; n];
+
+        for (c, members) in sccs.iter().enumerate() {
+            let mut set = BitSet::new(n);
+            let cyclic = members.len() > 1;
+            if cyclic {
+                members.iter().for_each(|idx| set.insert(idx.index()));
+            }
+
+            for sc in succs[c].iter() {
+                sccs[*sc].iter().for_each(|idx| set.insert(idx.index()));
+                if let Some(succ_set) = &sets[*sc] {
+                    set.union(succ_set);
+                }
+                depths[c] = depths[c].max(depths[*sc] + 1);
+
+                waiting[*sc] -= 1;
+                if waiting[*sc] == 0 {
+                    sets[*sc] = None;
+                }
+            }
+
+            let reached: Vec<usize> = set.iter().collect();
+            let total: u64 = reached.iter().map(|d| nodes[*d].size()).sum();
+            for idx in members {
+                let i = idx.index();
+
+                // Members of a cycle are in their own set, but do not count
+                // as their own dependency.
+                let (count, own) = if cyclic {
+                    (reached.len() - 1, nodes[i].size())
+                } else {
+                    (reached.len(), 0)
+                };
+                dependencies[i] = count as u32;
+                size[i] = total - own;
+                depth[i] = depths[c];
+
+                if nodes[i].node_type().is_executable() {
+                    for d in reached.iter().filter(|d| **d != i) {
+                        dependents[*d] += 1;
+                    }
+                }
+            }
+
+            if waiting[c] > 0 {
+                sets[c] = Some(set);
+            }
+        }
+
+        let dependent_executables = graph
+            .node_indices()
+            .map(|idx| {
+                nodes[idx.index()]
+                    .node_type()
+                    .is_library()
+                    .then_some(dependents[idx.index()])
+            })
+            .collect();
+
+        Self {
+            dependencies,
+            size,
+            depth,
+            dependent_executables,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::NodeType;
+
+    #[test]
+    fn collapses_cycles_into_shared_sets() {
+        // exe -> a -> b <-> c, exe -> c
+        let nodes: Vec<BinNode> = [
+            ("exe", NodeType::ELFBinary),
+            ("a", NodeType::ELFLibrary),
+            ("b", NodeType::ELFLibrary),
+            ("c", NodeType::ELFLibrary),
+        ]
+        .into_iter()
+        .map(|(name, t)| BinNode::new(name.to_string(), format!("/{}", name), t))
+        .collect();
+
+        let graph: DiGraph<(), ()> = DiGraph::from_edges([(0, 1), (1, 2), (2, 3), (3, 2), (0, 3)]);
+        let closure = Closure::compute(&nodes, &graph, &Components::compute(&graph));
+
+        assert_eq!(closure.dependencies, [3, 2, 1, 1]);
+        assert_eq!(closure.depth, [2, 1, 0, 0]);
+        assert_eq!(
+            closure.dependent_executables,
+            [None, Some(1), Some(1), Some(1)]
+        );
+    }
+}
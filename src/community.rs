@@ -0,0 +1,277 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::collections::BTreeMap;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::Serialize;
+
+/// Number of members listed in the summary of each community.
+const SUMMARY_MEMBERS: usize = 5;
+
+/// Passes over every node within a level before giving up on it settling.
+const MAX_PASSES: usize = 100;
+
+/// A Community summarizes one of the clusters found in the graph.
+#[derive(Debug, Serialize)]
+pub struct Community {
+    id: u32,
+    size: u32,
+
+    /// Names of the most central members, most central first.
+    central_members: Vec<String>,
+}
+
+impl Community {
+    pub fn new(id: u32, size: u32, central_members: Vec<String>) -> Self {
+        Self {
+            id,
+            size,
+            central_members: central_members.into_iter().take(SUMMARY_MEMBERS).collect(),
+        }
+    }
+}
+
+/// An undirected weighted graph, with self loops kept apart.
+#[derive(Clone)]
+struct Level {
+    adj: Vec<Vec<(usize, f64)>>,
+    loops: Vec<f64>,
+}
+
+impl Level {
+    fn degree(&self, i: usize) -> f64 {
+        self.adj[i].iter().map(|(_, w)| w).sum::<f64>() + 2. * self.loops[i]
+    }
+
+    /// Move nodes between communities while that raises the modularity.
+    /// Returns the community of every node and whether any node moved.
+    fn local_moves(&self, resolution: f64, rng: &mut StdRng) -> (Vec<usize>, bool) {
+        let n = self.adj.len();
+        let k: Vec<f64> = (0..n).map(|i| self.degree(i)).collect();
+        let m2: f64 = k.iter().sum();
+        let mut community: Vec<usize> = (0..n).collect();
+        let mut tot = k.clone();
+        if m2 == 0. {
+            return (community, false);
+        }
+
+        let mut order: Vec<usize> = (0..n).collect();
+        order.shuffle(rng);
+
+        let mut weight_to = vec![0.; n];
+        let mut touched: Vec<usize> = vec![];
+        let mut any_moved = false;
+        for _ in 0..MAX_PASSES {
+            let mut moved = false;
+            for i in order.iter().copied() {
+                let current = community[i];
+                tot[current] -= k[i];
+
+                for (j, w) in self.adj[i].iter() {
+                    let c = community[*j];
+                    if weight_to[c] == 0. {
+                        touched.push(c);
+                    }
+                    weight_to[c] += w;
+                }
+
+                let gain = |c: usize| weight_to[c] - resolution * tot[c] * k[i] / m2;
+                let mut best = current;
+                let mut best_gain = gain(current);
+                for c in touched.iter().copied() {
+                    if gain(c) > best_gain + 1e-12 {
+                        best = c;
+                        best_gain = gain(c);
+                    }
+                }
+
+                tot[best] += k[i];
+                community[i] = best;
+                moved |= best != current;
+
+                for c in touched.drain(..) {
+                    weight_to[c] = 0.;
+                }
+            }
+
+            any_moved |= moved;
+            if !moved {
+                break;
+            }
+        }
+
+        (community, any_moved)
+    }
+
+    /// Collapse every community into a single node of the next level.
+    fn aggregate(&self, community: &[usize], count: usize) -> Self {
+        let mut adj: Vec<BTreeMap<usize, f64>> = vec![BTreeMap::new(); count];
+        let mut loops = vec![0.; count];
+
+        for (i, edges) in self.adj.iter().enumerate() {
+            let ci = community[i];
+            loops[ci] += self.loops[i];
+            for (j, w) in edges.iter() {
+                let cj = community[*j];
+                if ci == cj {
+                    // Internal edges are seen from both ends.
+                    loops[ci] += w / 2.;
+                } else {
+                    *adj[ci].entry(cj).or_default() += w;
+                }
+            }
+        }
+
+        Self {
+            adj: adj.into_iter().map(|a| a.into_iter().collect()).collect(),
+            loops,
+        }
+    }
+}
+
+/// Relabel communities as 0..count, in order of first appearance.
+fn relabel(community: &mut [usize]) -> usize {
+    let mut labels: Vec<Option<usize>> = vec![None; community.len()];
+    let mut count = 0;
+    for c in community.iter_mut() {
+        *c = *labels[*c].get_or_insert_with(|| {
+            count += 1;
+            count - 1
+        });
+    }
+    count
+}
+
+/// Find communities with the Louvain method over the undirected projection
+/// of the edges, each edge adding one to the weight between its ends.
+/// Communities are numbered from the largest down. Also returns the
+/// modularity of the partition.
+pub fn louvain(
+    n: usize,
+    edges: impl Iterator<Item = (usize, usize)>,
+    resolution: f64,
+    seed: u64,
+) -> (Vec<u32>, f64) {
+    let mut adj: Vec<BTreeMap<usize, f64>> = vec![BTreeMap::new(); n];
+    for (s, t) in edges.filter(|(s, t)| s != t) {
+        *adj[s].entry(t).or_default() += 1.;
+        *adj[t].entry(s).or_default() += 1.;
+    }
+    let base = Level {
+        adj: adj.into_iter().map(|a| a.into_iter().collect()).collect(),
+        loops: vec![0.; n],
+    };
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut membership: Vec<usize> = (0..n).collect();
+    let mut level = base.clone();
+    loop {
+        let (mut community, moved) = level.local_moves(resolution, &mut rng);
+        if !moved {
+            break;
+        }
+        let count = relabel(&mut community);
+        for m in membership.iter_mut() {
+            *m = community[*m];
+        }
+        level = level.aggregate(&community, count);
+    }
+
+    // Number the communities by size, largest first.
+    let mut sizes = vec![0; n];
+    for m in membership.iter() {
+        sizes[*m] += 1;
+    }
+    let mut by_size: Vec<usize> = (0..n).filter(|c| sizes[*c] > 0).collect();
+    by_size.sort_by_key(|c| (std::cmp::Reverse(sizes[*c]), *c));
+    let mut ids = vec![0; n];
+    for (id, c) in by_size.iter().enumerate() {
+        ids[*c] = id as u32;
+    }
+    let communities: Vec<u32> = membership.iter().map(|m| ids[*m]).collect();
+    let q = modularity(&base, &communities, resolution);
+
+    (communities, q)
+}
+
+fn modularity(level: &Level, community: &[u32], resolution: f64) -> f64 {
+    let n = level.adj.len();
+    let m2: f64 = (0..n).map(|i| level.degree(i)).sum();
+    if m2 == 0. {
+        return 0.;
+    }
+
+    let mut internal = 0.;
+    let mut tot = vec![0.; n];
+    for i in 0..n {
+        tot[community[i] as usize] += level.degree(i);
+        for (j, w) in level.adj[i].iter() {
+            if community[i] == community[*j] {
+                internal += w;
+            }
+        }
+    }
+
+    internal / m2 - resolution * tot.iter().map(|t| (t / m2).powi(2)).sum::<f64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two triangles joined by a single edge.
+    const BARBELL: [(usize, usize); 7] = [(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (2, 3)];
+
+    #[test]
+    fn separates_loosely_joined_cliques() {
+        let (communities, q) = louvain(6, BARBELL.into_iter(), 1., 0);
+
+        assert_eq!(communities[0], communities[1]);
+        assert_eq!(communities[1], communities[2]);
+        assert_eq!(communities[3], communities[4]);
+        assert_eq!(communities[4], communities[5]);
+        assert_ne!(communities[0], communities[3]);
+
+        // Each triangle holds 6 of the 14 edge ends internally, and half of
+        // the degree: 2 * (6/14 - (7/14)^2).
+        assert!((q - 5. / 14.).abs() < 1e-9, "modularity {}", q);
+    }
+
+    #[test]
+    fn numbers_communities_largest_first() {
+        // A triangle plus an isolated edge and an isolated node.
+        let edges = [(0, 1), (1, 2), (2, 0), (3, 4)];
+        let (communities, _) = louvain(6, edges.into_iter(), 1., 0);
+        assert_eq!(communities, [0, 0, 0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn low_resolution_merges_everything() {
+        let (communities, q) = louvain(6, BARBELL.into_iter(), 0., 0);
+        assert!(communities.iter().all(|c| *c == 0));
+        assert!((q - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn empty_graph_has_zero_modularity() {
+        let (communities, q) = louvain(3, std::iter::empty(), 1., 0);
+        assert_eq!(communities.len(), 3);
+        assert_eq!(q, 0.);
+    }
+}
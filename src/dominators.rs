@@ -0,0 +1,134 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use rustworkx_core::petgraph::{
+    algo::dominators::simple_fast,
+    graph::{DiGraph, NodeIndex},
+    visit::Dfs,
+};
+
+use crate::node::{BinNode, EdgeType};
+
+/// Dominance holds the per-node dominator measures of a graph. A node
+/// dominates another when every path from the root to it passes through
+/// the node.
+pub struct Dominance {
+    /// For libraries, the executables in whose dominator tree the library
+    /// dominates at least one other node.
+    pub dominated_executables: Vec<Option<u32>>,
+
+    /// The size of each node's subtree in the dominator tree rooted at a
+    /// virtual root over every executable, excluding the node itself.
+    pub dominated_nodes: Vec<u32>,
+}
+
+impl Dominance {
+    pub fn compute(graph: &DiGraph<BinNode, EdgeType>) -> Self {
+        println!("computing dominator trees for graph");
+        let n = graph.node_count();
+        let executables: Vec<NodeIndex> = graph
+            .node_indices()
+            .filter(|idx| graph[*idx].node_type().is_executable())
+            .collect();
+
+        // Each executable only reaches its own closure, so rooting a tree at
+        // every one of them stays cheap.
+        let mut dominated = vec![0; n];
+        let mut marked: Vec<Option<NodeIndex>> = vec![None; n];
+        for root in executables.iter() {
+            let tree = simple_fast(graph, *root);
+            let mut dfs = Dfs::new(graph, *root);
+            while let Some(idx) = dfs.next(graph) {
+                let Some(idom) = tree.immediate_dominator(idx) else {
+                    continue;
+                };
+                if idom != *root && marked[idom.index()] != Some(*root) {
+                    marked[idom.index()] = Some(*root);
+                    dominated[idom.index()] += 1;
+                }
+            }
+        }
+
+        // Link a virtual root to every executable, so a single tree covers
+        // everything reachable from any of them.
+        let mut virtual_graph = graph.map(|_, _| (), |_, _| ());
+        let virtual_root = virtual_graph.add_node(());
+        for exe in executables.iter() {
+            virtual_graph.add_edge(virtual_root, *exe, ());
+        }
+
+        let tree = simple_fast(&virtual_graph, virtual_root);
+        let mut dominated_nodes = vec![0; n];
+        for idx in graph.node_indices() {
+            let Some(dominators) = tree.strict_dominators(idx) else {
+                continue;
+            };
+            for d in dominators.filter(|d| *d != virtual_root) {
+                dominated_nodes[d.index()] += 1;
+            }
+        }
+
+        let dominated_executables = graph
+            .node_indices()
+            .map(|idx| {
+                graph[idx]
+                    .node_type()
+                    .is_library()
+                    .then_some(dominated[idx.index()])
+            })
+            .collect();
+
+        Self {
+            dominated_executables,
+            dominated_nodes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::NodeType;
+
+    #[test]
+    fn counts_dominated_executables_and_nodes() {
+        // exe1 -> a -> b -> c, exe1 -> d, exe2 -> b
+        let mut graph: DiGraph<BinNode, EdgeType> = DiGraph::new();
+        let mut add = |name: &str, t: NodeType| {
+            graph.add_node(BinNode::new(name.to_string(), format!("/{}", name), t))
+        };
+        let exe1 = add("exe1", NodeType::ELFBinary);
+        let exe2 = add("exe2", NodeType::ELFBinary);
+        let a = add("a", NodeType::ELFLibrary);
+        let b = add("b", NodeType::ELFLibrary);
+        let c = add("c", NodeType::ELFLibrary);
+        let d = add("d", NodeType::ELFLibrary);
+        for (s, t) in [(exe1, a), (a, b), (b, c), (exe1, d), (exe2, b)] {
+            graph.add_edge(s, t, EdgeType::Needed);
+        }
+
+        let dominance = Dominance::compute(&graph);
+        assert_eq!(
+            dominance.dominated_executables,
+            [None, None, Some(1), Some(2), Some(0), Some(0)]
+        );
+
+        // b is reachable from both executables, so only c stays under it.
+        assert_eq!(dominance.dominated_nodes, [2, 0, 0, 1, 0, 0]);
+    }
+}
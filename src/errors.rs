@@ -19,14 +19,19 @@
 use std::{fmt::Display, io};
 
 use goblin::error::Error;
+use rusqlite::Error as SqliteError;
 use serde_json::Error as SerdeJSONError;
+use zip::result::ZipError;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum BingraphError {
     GeneralError(String),
     IOError(io::Error),
     GoblinError(Error),
     SerdeError(SerdeJSONError),
+    ZipError(ZipError),
+    SqliteError(SqliteError),
 }
 
 impl Display for BingraphError {
@@ -36,6 +41,8 @@ impl Display for BingraphError {
             Self::IOError(e) => write!(f, "io: {}", e),
             Self::GoblinError(e) => write!(f, "goblin: {}", e),
             Self::SerdeError(e) => write!(f, "serde_json: {}", e),
+            Self::ZipError(e) => write!(f, "zip: {}", e),
+            Self::SqliteError(e) => write!(f, "sqlite: {}", e),
         }
     }
 }
@@ -58,6 +65,18 @@ impl From<SerdeJSONError> for BingraphError {
     }
 }
 
+impl From<ZipError> for BingraphError {
+    fn from(value: ZipError) -> Self {
+        Self::ZipError(value)
+    }
+}
+
+impl From<SqliteError> for BingraphError {
+    fn from(value: SqliteError) -> Self {
+        Self::SqliteError(value)
+    }
+}
+
 impl From<String> for BingraphError {
     fn from(value: String) -> Self {
         Self::GeneralError(value)
@@ -16,7 +16,10 @@
 *	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
  */
 
-use std::collections::HashMap;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+};
 
 use serde::Serialize;
 
@@ -27,123 +30,395 @@ use rustworkx_core::{
     petgraph::{adj::NodeIndex, graph::DiGraph, visit::IntoNodeReferences},
 };
 
-use crate::{errors::BingraphError, node::BinNode, pathiter::PathIterator};
+use crate::{
+    betweenness,
+    closure::Closure,
+    community::{self, Community},
+    dominators::Dominance,
+    errors::BingraphError,
+    initorder, live,
+    metrics::{with_threads, Adjacency, Method, Metric, MetricRun, MetricSpec, MetricStatus},
+    node::{BinNode, EdgeType},
+    package::PackageDb,
+    pathiter::PathIterator,
+    pe::LoaderTables,
+    python::VendoredLibraries,
+    resolver::Resolver,
+    scc::Components,
+};
+
+/// One end of an edge while the graph is being built: the node index and
+/// the node name.
+type EdgeEnd = (NodeIndex, String);
+
+/// A BinEdge is a single typed link between two nodes in the graph.
+#[derive(Debug, Serialize, Clone)]
+pub struct BinEdge {
+    source: String,
+    target: String,
+    kind: EdgeType,
+
+    /// Indices of the source and target within the graph nodes.
+    #[serde(skip)]
+    source_idx: usize,
+    #[serde(skip)]
+    target_idx: usize,
+
+    /// Whether a running process was seen using this edge, only set when
+    /// runtime information was collected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    observed: Option<bool>,
+}
+
+impl BinEdge {
+    pub fn kind(&self) -> EdgeType {
+        self.kind
+    }
+
+    pub fn source_idx(&self) -> usize {
+        self.source_idx
+    }
+
+    pub fn target_idx(&self) -> usize {
+        self.target_idx
+    }
+}
+
+/// The centralities computed for every node of a graph, by node index.
+/// Metrics that were not selected, or that failed, have no values.
+pub struct Centralities {
+    betweenness: Vec<Option<f64>>,
+    katz: Option<Vec<f64>>,
+    eigen: Option<Vec<f64>>,
+    closeness: Vec<Option<f64>>,
+
+    /// How each selected centrality was computed.
+    pub runs: Vec<MetricRun>,
+}
+
+impl Centralities {
+    /// Compute the selected centralities over the graph, weighting edges with
+    /// `weight` where the measure supports it.
+    pub fn compute<N: Sync, E: Sync>(
+        graph: &DiGraph<N, E>,
+        weight: impl Fn(&E) -> f64,
+        metrics: &[MetricSpec],
+    ) -> Self {
+        let mut c = Self {
+            betweenness: vec![],
+            katz: None,
+            eigen: None,
+            closeness: vec![],
+            runs: vec![],
+        };
+
+        for spec in metrics {
+            let p = &spec.params;
+            match spec.metric {
+                Metric::Betweenness => {
+                    println!("computing betweeness centrality for graph");
+                    let (betweenness, run) =
+                        with_threads(p.threads, || Self::betweenness_of(graph, spec));
+                    c.betweenness = betweenness;
+                    c.runs.push(run);
+                }
+                Metric::Katz => {
+                    println!("computing katz centrality for graph");
+                    let katz = katz_centrality(
+                        graph,
+                        |e| Ok::<f64, BingraphError>(weight(e.weight())),
+                        p.alpha,
+                        None,
+                        p.beta,
+                        p.max_iter,
+                        p.tolerance,
+                    );
+                    c.runs.push(Self::iterative_run(spec, &katz));
+                    c.katz = katz.ok().flatten();
+                }
+                Metric::Eigenvector => {
+                    println!("computing eigenvector centrality for graph");
+                    let eigen = eigenvector_centrality(
+                        graph,
+                        |e| Ok::<f64, BingraphError>(weight(e.weight())),
+                        p.max_iter,
+                        p.tolerance,
+                    );
+                    c.runs.push(Self::iterative_run(spec, &eigen));
+                    c.eigen = eigen.ok().flatten();
+                }
+                Metric::Closeness => {
+                    println!("computing closeness centrality for graph");
+                    c.closeness = with_threads(p.threads, || {
+                        closeness_centrality(graph, p.normalized.unwrap_or(true))
+                    });
+                    c.runs.push(MetricRun::new(spec, MetricStatus::Computed));
+                }
+                _ => {}
+            }
+        }
+
+        c
+    }
+
+    /// Compute betweenness exactly, or sample source nodes when asked to or
+    /// when the exact computation is expected to exceed the time budget.
+    fn betweenness_of<N: Sync, E: Sync>(
+        graph: &DiGraph<N, E>,
+        spec: &MetricSpec,
+    ) -> (Vec<Option<f64>>, MetricRun) {
+        let mut p = spec.params.clone();
+        let endpoints = p.endpoints.unwrap_or(true);
+        let normalized = p.normalized.unwrap_or(true);
+        let seed = p.seed.unwrap_or_default();
+
+        // With a budget, samples are only taken when falling back.
+        if let Some(budget) = p.budget {
+            let estimate = betweenness::estimate_exact_seconds(graph, seed);
+            println!(
+                "exact betweenness estimated to take {:.1}s of a {:.1}s budget",
+                estimate, budget
+            );
+
+            if estimate <= budget {
+                p.samples = None;
+            } else if p.samples.is_none() {
+                let per_source = estimate / graph.node_count().max(1) as f64;
+                p.samples = Some((budget / per_source) as usize);
+            }
+        }
+
+        match p.samples {
+            Some(samples) => {
+                let samples = samples.max(1).min(graph.node_count());
+                println!("sampling betweenness from {} source nodes", samples);
+                p.samples = Some(samples);
+                p.seed = Some(seed);
+                let values = betweenness::sampled(graph, samples, seed, endpoints, normalized);
+                let run =
+                    MetricRun::new(spec, MetricStatus::Computed).with_method(Method::Sampled, p);
+                (values, run)
+            }
+            None => {
+                let values = betweenness_centrality(graph, endpoints, normalized, 4);
+                let run =
+                    MetricRun::new(spec, MetricStatus::Computed).with_method(Method::Exact, p);
+                (values, run)
+            }
+        }
+    }
+
+    /// Record the outcome of a centrality that iterates until it converges,
+    /// printing it when it did not.
+    fn iterative_run(
+        spec: &MetricSpec,
+        result: &Result<Option<Vec<f64>>, BingraphError>,
+    ) -> MetricRun {
+        match result {
+            Ok(Some(_)) => MetricRun::new(spec, MetricStatus::Converged),
+            Ok(None) => {
+                println!("{} centrality did not converge", spec.metric.name());
+                MetricRun::new(spec, MetricStatus::NotConverged)
+            }
+            Err(e) => {
+                println!("unable to compute {} centrality: {}", spec.metric.name(), e);
+                MetricRun::failed(spec, e.to_string())
+            }
+        }
+    }
+
+    pub fn betweenness(&self, idx: usize) -> Option<f64> {
+        self.betweenness.get(idx).copied().flatten()
+    }
+
+    pub fn katz(&self, idx: usize) -> Option<f64> {
+        self.katz.as_ref()?.get(idx).copied()
+    }
+
+    pub fn eigen(&self, idx: usize) -> Option<f64> {
+        self.eigen.as_ref()?.get(idx).copied()
+    }
+
+    pub fn closeness(&self, idx: usize) -> Option<f64> {
+        self.closeness.get(idx).copied().flatten()
+    }
+}
 
 #[derive(Debug, Serialize)]
 pub struct BinGraph {
     nodes: Vec<BinNode>,
 
-    edges: Vec<(String, String)>,
+    edges: Vec<BinEdge>,
     degree_distribution: HashMap<u32, u32>,
 
     average_degree: f64,
     num_nodes: u32,
     num_edges: u32,
-}
 
-impl BinGraph {
-    pub fn new(bin_path: String, lib_path: String) -> Result<Self, BingraphError> {
-        let mut edges: Vec<((NodeIndex, String), (NodeIndex, String))> = vec![];
-        let mut ext_nodes: Vec<BinNode> = vec![];
+    /// The absolute paths of the members of every strongly connected
+    /// component containing a dependency cycle.
+    cyclic_components: Vec<Vec<String>>,
 
-        // Mapping of BinNode names and their corresponding node index.
-        let mut nodes: HashMap<String, NodeIndex> = HashMap::new();
-        let mut node_indegree: HashMap<String, u32> = HashMap::new();
-        let mut graph: DiGraph<BinNode, u32> = rustworkx_core::petgraph::Graph::new();
+    /// A shortest cycle through each of those components, as the absolute
+    /// paths along it from its first member back to that member.
+    cycles: Vec<Vec<String>>,
+
+    /// How each selected metric was computed.
+    metrics: Vec<MetricRun>,
+
+    /// Every detected community, largest first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    communities: Vec<Community>,
+
+    /// The libraries Python distributions bundle, in python mode.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    vendored_libraries: Vec<VendoredLibraries>,
+}
 
+impl BinGraph {
+    /// Construct the graph from every file within the binary and library
+    /// paths, along with any nodes collected elsewhere such as services.
+    pub fn new(
+        bin_path: String,
+        lib_path: String,
+        extra_nodes: Vec<BinNode>,
+        loader: &LoaderTables,
+    ) -> Result<Self, BingraphError> {
         let mut total_path = bin_path;
         total_path.push(':');
         total_path.push_str(&lib_path);
 
-        println!("searching through {} for things", total_path);
+        let mut nodes = Self::scan(&total_path);
+        nodes.extend(extra_nodes);
+        Self::from_nodes_with_loader(nodes, loader)
+    }
+
+    /// Create a node from every file found within the provided system path.
+    pub fn scan(path: &str) -> Vec<BinNode> {
+        println!("searching through {} for things", path);
 
-        // Go through every file and try to add it as a node, and add a reference
-        // from the name to the nodeindex.
-        for path in PathIterator::new(&total_path) {
+        // Go through every file and try to create a node from it.
+        let mut bin_nodes: Vec<BinNode> = vec![];
+        for path in PathIterator::new(path) {
             let s = path.path();
-            match BinNode::try_from(path) {
-                Ok(node) => {
-                    // println!("created node at {:?}", s);
-                    let nnode = node.clone();
-                    let idx = graph.add_node(node);
-                    nodes.insert(nnode.name(), idx.index() as u32);
-                    node_indegree.insert(nnode.name(), 0);
-                }
+            match BinNode::from_entry(path) {
+                Ok(nodes) => bin_nodes.extend(nodes),
                 Err(e) => println!("unable to create node at {:?}: {}", s, e),
             }
         }
 
+        bin_nodes
+    }
+
+    /// Construct the graph, its links and all metrics from an already
+    /// collected set of nodes.
+    pub fn from_nodes(bin_nodes: Vec<BinNode>) -> Result<Self, BingraphError> {
+        Self::from_nodes_with_loader(bin_nodes, &LoaderTables::default())
+    }
+
+    /// Construct the graph from already collected nodes, resolving PE imports
+    /// against the given Windows loader tables.
+    pub fn from_nodes_with_loader(
+        bin_nodes: Vec<BinNode>,
+        loader: &LoaderTables,
+    ) -> Result<Self, BingraphError> {
+        let mut edges: Vec<(EdgeEnd, EdgeEnd, EdgeType)> = vec![];
+        let mut ext_nodes: Vec<BinNode> = vec![];
+
+        let mut node_indegree: HashMap<String, u32> = HashMap::new();
+        let mut graph: DiGraph<BinNode, EdgeType> = rustworkx_core::petgraph::Graph::new();
+
+        // Add every node to the graph.
+        for node in bin_nodes {
+            node_indegree.insert(node.name(), 0);
+            graph.add_node(node);
+        }
+
+        // Shared library exports are only read when there are static
+        // archives or objects to link against them.
+        if graph.node_weights().any(|n| !n.members().is_empty()) {
+            for node in graph.node_weights_mut() {
+                node.load_exports();
+            }
+        }
+
+        let resolver = Resolver::new(&graph, loader);
+
+        // Static archives and objects declare no libraries, so link them to the
+        // libraries exporting the symbols they leave undefined.
+        for idx in graph.node_indices() {
+            if !graph[idx].members().is_empty() {
+                let deps = resolver.link_dependencies(&graph, &graph[idx]);
+                graph[idx].add_dependencies(deps);
+            }
+        }
+
         // Iterate through every node and find it's dependencies, add the links.
         for (sidx, node) in graph.node_references() {
             for neigh in node.get_dependencies() {
-                if let Some((name, didx)) = nodes.get_key_value(neigh) {
-                    edges.push(((sidx.index() as u32, node.name()), (*didx, name.clone())));
+                if let Some(didx) = resolver.resolve(&graph, node, neigh) {
+                    let name = graph[didx].name();
+                    edges.push((
+                        (sidx.index() as u32, node.name()),
+                        (didx.index() as u32, name.clone()),
+                        neigh.kind,
+                    ));
 
                     // Keep track of the indegree for each node as well.
-                    if let Some(v) = node_indegree.get(name) {
-                        node_indegree.insert(name.clone(), v + 1);
+                    if let Some(v) = node_indegree.get(&name) {
+                        node_indegree.insert(name, v + 1);
                     }
                 }
             }
         }
 
         // Add the edges to the main graph structure too.
-        for (src, dst) in edges.iter() {
-            graph.add_edge(NodeIndex::from(src.0), NodeIndex::from(dst.0), 0);
-        }
-
-        // Compute our centralities
-        println!("computing betweeness centrality for graph");
-        let betweenness = betweenness_centrality(&graph, true, true, 4);
-        println!("computing katz centrality for graph");
-        let katz = match katz_centrality(
-            &graph,
-            |_| Ok::<f64, BingraphError>(1.),
-            None,
-            None,
-            None,
-            Some(100),
-            None,
-        ) {
-            Ok(k) => k,
-            Err(e) => {
-                println!("unable to compute katz centrality: {}", e);
-                None
-            }
-        };
-        println!("computing eigenvector centrality for graph");
-        let eigen =
-            match eigenvector_centrality(&graph, |_| Ok::<f64, BingraphError>(1.), Some(100), None)
-            {
-                Ok(e) => e,
-                Err(e) => {
-                    println!("unable to compute eigenvector_centrality: {}", e);
-                    None
-                }
-            };
-        println!("computing closeness centrality for graph");
-        let closeness = closeness_centrality(&graph, true);
+        for (src, dst, kind) in edges.iter() {
+            graph.add_edge(NodeIndex::from(src.0), NodeIndex::from(dst.0), *kind);
+        }
+
+        println!("computing strongly connected components for graph");
+        let components = Components::compute(&graph);
+        let dominance = Dominance::compute(&graph);
+        let init_layers = initorder::layers(&graph);
 
-        // Assign centralities to the new nodes and append to
+        let cyclic: Vec<usize> = (0..components.members.len())
+            .filter(|c| components.is_cycle(&graph, *c))
+            .collect();
+        let cyclic_components: Vec<Vec<String>> = cyclic
+            .iter()
+            .map(|c| {
+                components.members[*c]
+                    .iter()
+                    .map(|idx| graph[*idx].absolute_path().to_string())
+                    .collect()
+            })
+            .collect();
+        let cycles: Vec<Vec<String>> = cyclic
+            .iter()
+            .filter_map(|c| components.cycle(&graph, *c))
+            .map(|cycle| {
+                cycle
+                    .iter()
+                    .map(|idx| graph[*idx].absolute_path().to_string())
+                    .collect()
+            })
+            .collect();
+        if !cycles.is_empty() {
+            println!("found {} dependency cycles", cycles.len());
+        }
+
+        // Assign the per-node measures to the new nodes and append to
         for (idx, node) in graph.node_references() {
             let mut new_node = node.clone();
-            if let Some(Some(value)) = betweenness.get(idx.index()) {
-                new_node.set_betweeness_centrality(*value);
-            }
-            if let Some(ref vec) = katz {
-                if let Some(value) = vec.get(idx.index()) {
-                    new_node.set_katz_centrality(*value);
-                }
-            }
 
-            if let Some(ref vec) = eigen {
-                if let Some(value) = vec.get(idx.index()) {
-                    new_node.set_eigen_centrality(*value);
-                }
-            }
-            if let Some(Some(value)) = closeness.get(idx.index()) {
-                new_node.set_closeness_centrality(*value);
+            let i = idx.index();
+            if let Some(n) = dominance.dominated_executables[i] {
+                new_node.set_dominated_executables(n);
             }
+            new_node.set_dominated_nodes(dominance.dominated_nodes[i]);
+            new_node.set_init_layer(init_layers[i]);
+            new_node.set_scc_id(components.component[i] as u32);
 
             // Specify the outdegree of the node.
             new_node.set_out_degree(new_node.get_dependencies().len() as u32);
@@ -156,9 +431,16 @@ impl BinGraph {
             ext_nodes.push(new_node);
         }
 
-        let ext_edges: Vec<(String, String)> = edges
+        let ext_edges: Vec<BinEdge> = edges
             .iter()
-            .map(|x| (x.0 .1.clone(), x.1 .1.clone()))
+            .map(|x| BinEdge {
+                source: x.0 .1.clone(),
+                target: x.1 .1.clone(),
+                kind: x.2,
+                source_idx: x.0 .0 as usize,
+                target_idx: x.1 .0 as usize,
+                observed: None,
+            })
             .collect();
 
         let num_nodes = graph.node_count() as u32;
@@ -179,34 +461,349 @@ impl BinGraph {
             }
         }
 
-        return Ok(Self {
+        Ok(Self {
             nodes: ext_nodes,
             edges: ext_edges,
-            num_nodes: num_nodes,
-            num_edges: num_edges,
+            num_nodes,
+            num_edges,
             average_degree: avg_degree,
             degree_distribution: deg_dist,
-        });
+            cyclic_components,
+            cycles,
+            metrics: vec![],
+            communities: vec![],
+            vendored_libraries: vec![],
+        })
+    }
+
+    pub fn set_vendored_libraries(&mut self, vendored: Vec<VendoredLibraries>) {
+        self.vendored_libraries = vendored;
+    }
+
+    pub fn nodes(&self) -> &Vec<BinNode> {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> &Vec<BinEdge> {
+        &self.edges
+    }
+
+    /// Find the indices of every node matching a query, given as a node
+    /// name or as a path to the file the node was created from.
+    pub fn find_nodes(&self, query: &str) -> Vec<usize> {
+        let path = live::canonical(query);
+        (0..self.nodes.len())
+            .filter(|i| {
+                let n = &self.nodes[*i];
+                n.name() == query
+                    || n.absolute_path() == query
+                    || live::canonical(n.absolute_path()) == path
+            })
+            .collect()
+    }
+
+    /// Compute the metrics selected on the command line for every node,
+    /// recording how each of them went.
+    pub fn compute_metrics(&mut self, metrics: &[MetricSpec]) {
+        let mut graph: DiGraph<(), ()> = DiGraph::new();
+        for _ in self.nodes.iter() {
+            graph.add_node(());
+        }
+        for e in self.edges.iter() {
+            graph.add_edge(
+                (e.source_idx as u32).into(),
+                (e.target_idx as u32).into(),
+                (),
+            );
+        }
+
+        let centralities = Centralities::compute(&graph, |_| 1., metrics);
+        for (i, node) in self.nodes.iter_mut().enumerate() {
+            if let Some(value) = centralities.betweenness(i) {
+                node.set_betweeness_centrality(value);
+            }
+            if let Some(value) = centralities.katz(i) {
+                node.set_katz_centrality(value);
+            }
+            if let Some(value) = centralities.eigen(i) {
+                node.set_eigen_centrality(value);
+            }
+            if let Some(value) = centralities.closeness(i) {
+                node.set_closeness_centrality(value);
+            }
+        }
+        self.metrics = centralities.runs;
+
+        let adjacency = Adjacency::new(
+            self.nodes.len(),
+            self.edges.iter().map(|e| (e.source_idx, e.target_idx)),
+        );
+
+        for spec in metrics {
+            match spec.metric {
+                Metric::Pagerank => {
+                    println!("computing pagerank for graph");
+                    let (ranks, iterations) = adjacency.pagerank_in(&spec.params);
+                    let (reverse, reverse_iterations) = adjacency.pagerank_out(&spec.params);
+                    if let (Some(ranks), Some(reverse)) = (&ranks, &reverse) {
+                        for (i, node) in self.nodes.iter_mut().enumerate() {
+                            node.set_pagerank(ranks[i]);
+                            node.set_reverse_pagerank(reverse[i]);
+                        }
+                    }
+                    self.metrics.push(MetricRun::iterative(
+                        spec,
+                        iterations.max(reverse_iterations),
+                        ranks.is_some() && reverse.is_some(),
+                    ));
+                }
+                Metric::Hits => {
+                    println!("computing hits scores for graph");
+                    let (scores, iterations) = adjacency.hits(&spec.params);
+                    if let Some((hubs, authorities)) = &scores {
+                        for (i, node) in self.nodes.iter_mut().enumerate() {
+                            node.set_hub_score(hubs[i]);
+                            node.set_authority_score(authorities[i]);
+                        }
+                    }
+                    self.metrics
+                        .push(MetricRun::iterative(spec, iterations, scores.is_some()));
+                }
+                Metric::Kcore => {
+                    println!("computing core numbers for graph");
+                    let cores = adjacency.core_numbers();
+                    let in_cores = adjacency.in_core_numbers();
+                    let out_cores = adjacency.out_core_numbers();
+                    for (i, node) in self.nodes.iter_mut().enumerate() {
+                        node.set_core_number(cores[i]);
+                        node.set_in_core_number(in_cores[i]);
+                        node.set_out_core_number(out_cores[i]);
+                    }
+                    self.metrics
+                        .push(MetricRun::new(spec, MetricStatus::Computed));
+                }
+                Metric::Closure => {
+                    let components = Components::compute(&graph);
+                    let closure = Closure::compute(&self.nodes, &graph, &components);
+                    for (i, node) in self.nodes.iter_mut().enumerate() {
+                        node.set_transitive_dependencies(closure.dependencies[i]);
+                        node.set_transitive_size(closure.size[i]);
+                        node.set_max_depth(closure.depth[i]);
+                        if let Some(n) = closure.dependent_executables[i] {
+                            node.set_dependent_executables(n);
+                        }
+                    }
+                    self.metrics
+                        .push(MetricRun::new(spec, MetricStatus::Computed));
+                }
+                Metric::Community => {
+                    println!("detecting communities for graph");
+                    let (communities, modularity) = community::louvain(
+                        self.nodes.len(),
+                        self.edges.iter().map(|e| (e.source_idx, e.target_idx)),
+                        spec.params.resolution.unwrap_or(1.),
+                        spec.params.seed.unwrap_or_default(),
+                    );
+                    for (i, node) in self.nodes.iter_mut().enumerate() {
+                        node.set_community(communities[i]);
+                    }
+                    self.summarize_communities();
+                    println!(
+                        "found {} communities with modularity {:.3}",
+                        self.communities.len(),
+                        modularity
+                    );
+                    self.metrics
+                        .push(MetricRun::new(spec, MetricStatus::Computed));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Summarize each community by its most central members, by betweenness
+    /// when it was computed and otherwise by in-degree.
+    fn summarize_communities(&mut self) {
+        let mut members: BTreeMap<u32, Vec<&BinNode>> = BTreeMap::new();
+        for node in self.nodes.iter() {
+            if let Some(c) = node.community() {
+                members.entry(c).or_default().push(node);
+            }
+        }
+
+        self.communities = members
+            .into_iter()
+            .map(|(id, mut nodes)| {
+                nodes.sort_by(|a, b| {
+                    let key = |n: &BinNode| {
+                        (
+                            n.betweenness_centrality().unwrap_or_default(),
+                            n.get_in_degree(),
+                        )
+                    };
+                    key(b).partial_cmp(&key(a)).unwrap_or(Ordering::Equal)
+                });
+                Community::new(
+                    id,
+                    nodes.len() as u32,
+                    nodes.iter().map(|n| n.name()).collect(),
+                )
+            })
+            .collect();
+    }
+
+    /// Mark every node and edge as observed or not at runtime, given
+    /// predicates over the canonical paths of nodes and of edge ends.
+    pub fn mark_observed(
+        &mut self,
+        node_observed: impl Fn(&str) -> bool,
+        edge_observed: impl Fn(&str, &str) -> bool,
+    ) {
+        let paths: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|n| live::canonical(n.absolute_path()))
+            .collect();
+
+        for (node, path) in self.nodes.iter_mut().zip(paths.iter()) {
+            node.set_observed(node_observed(path));
+        }
+
+        for edge in self.edges.iter_mut() {
+            let (s, t) = (&paths[edge.source_idx], &paths[edge.target_idx]);
+            edge.observed = Some(edge_observed(s, t));
+        }
+
+        let used = self
+            .edges
+            .iter()
+            .filter(|e| e.observed == Some(true))
+            .count();
+        println!(
+            "{} of {} edges were observed in use",
+            used,
+            self.edges.len()
+        );
+    }
+
+    /// Attach the owning package to every node, returning how many nodes are
+    /// not owned by any package.
+    pub fn attach_packages(&mut self, db: &PackageDb) -> usize {
+        for node in self.nodes.iter_mut() {
+            node.set_package(db.owner(node.absolute_path()).cloned());
+        }
+
+        self.nodes.iter().filter(|n| n.package().is_none()).count()
     }
 
     pub fn serialize_graphviz(&self) -> String {
+        self.serialize_graphviz_of(|_| true)
+    }
+
+    /// Serialize the subgraph induced by the nodes whose index is kept.
+    pub fn serialize_graphviz_of(&self, keep: impl Fn(usize) -> bool) -> String {
         let mut graph: String = "".to_string();
 
         graph.push_str("digraph bingraph {\n\n");
 
-        for node in self.nodes.iter() {
-            let n = node.format_graphviz();
-            graph.push_str(&n);
+        // Nodes in a community are drawn within a cluster of their own.
+        let mut clusters: BTreeMap<u32, String> = BTreeMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !keep(i) {
+                continue;
+            }
+            match node.community() {
+                Some(c) => clusters
+                    .entry(c)
+                    .or_default()
+                    .push_str(&node.format_graphviz()),
+                None => graph.push_str(&node.format_graphviz()),
+            }
+        }
+
+        for (c, nodes) in clusters.iter() {
+            graph.push_str(&format!(
+                "  subgraph cluster_{} {{\n  label=\"community {}\"\n{}  }}\n",
+                c, c, nodes
+            ));
+        }
+
+        self.push_graphviz_edges(&mut graph, keep);
+        graph
+    }
+
+    /// Serialize the subgraph induced by the nodes whose index is kept, with
+    /// the nodes of each initialization layer drawn on the same rank.
+    pub fn serialize_graphviz_layers_of(&self, keep: impl Fn(usize) -> bool) -> String {
+        let mut graph: String = "".to_string();
+
+        graph.push_str("digraph bingraph {\n\n");
+
+        let mut layers: BTreeMap<u32, String> = BTreeMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if keep(i) {
+                layers
+                    .entry(node.init_layer())
+                    .or_default()
+                    .push_str(&node.format_graphviz());
+            }
+        }
+
+        for (layer, nodes) in layers.iter() {
+            graph.push_str(&format!(
+                "  subgraph layer_{} {{\n  rank=same\n{}  }}\n",
+                layer, nodes
+            ));
         }
 
+        self.push_graphviz_edges(&mut graph, keep);
+        graph
+    }
+
+    /// Append the edges between kept nodes and close the graph.
+    fn push_graphviz_edges(&self, graph: &mut String, keep: impl Fn(usize) -> bool) {
         graph.push_str("\n\n");
 
         for edge in self.edges.iter() {
-            let e = format!("  \"{}\" -> \"{}\"\n", edge.0, edge.1);
+            if !keep(edge.source_idx) || !keep(edge.target_idx) {
+                continue;
+            }
+            let e = format!(
+                "  \"{}\" -> \"{}\" [style={}]\n",
+                edge.source,
+                edge.target,
+                edge.kind.format_graphviz()
+            );
             graph.push_str(&e);
         }
 
         graph.push_str("\n}");
-        return graph;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_centralities_that_do_not_converge() {
+        let graph: DiGraph<(), ()> = DiGraph::from_edges([(0, 1), (1, 2), (0, 2), (2, 3)]);
+        let specs: Vec<MetricSpec> = ["katz:max-iter=1", "eigenvector:max-iter=1", "closeness"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+
+        let c = Centralities::compute(&graph, |_| 1., &specs);
+        let statuses: Vec<serde_json::Value> = c
+            .runs
+            .iter()
+            .map(|r| serde_json::to_value(r).unwrap()["status"].clone())
+            .collect();
+        assert_eq!(statuses, ["not_converged", "not_converged", "computed"]);
+
+        // Values are left unset rather than taken from the last iteration.
+        assert_eq!(c.katz(0), None);
+        assert_eq!(c.eigen(0), None);
+        assert!(c.closeness(3).is_some());
     }
 }
@@ -0,0 +1,197 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use serde::Serialize;
+
+use crate::{errors::BingraphError, graph::BinGraph, node::NodeType};
+
+/// The paths of affected nodes, split by what kind of node they are.
+#[derive(Debug, Serialize, Default)]
+pub struct AffectedSet {
+    executables: Vec<String>,
+    services: Vec<String>,
+    libraries: Vec<String>,
+}
+
+impl AffectedSet {
+    fn push(&mut self, node_type: &NodeType, path: String) {
+        match node_type {
+            t if t.is_executable() => self.executables.push(path),
+            NodeType::Service | NodeType::Process => self.services.push(path),
+            _ => self.libraries.push(path),
+        }
+    }
+}
+
+/// The nodes of a single package that are affected.
+#[derive(Debug, Serialize, Default)]
+pub struct PackageImpact {
+    direct: Vec<String>,
+    transitive: Vec<String>,
+}
+
+/// An ImpactReport lists every node that would lose a dependency if the
+/// target libraries were removed or replaced.
+#[derive(Debug, Serialize)]
+pub struct ImpactReport {
+    targets: Vec<String>,
+
+    /// Nodes depending on one of the targets themselves.
+    direct: AffectedSet,
+
+    /// Nodes only depending on the targets through other nodes.
+    transitive: AffectedSet,
+
+    /// Affected nodes grouped by their owning package, when known.
+    packages: BTreeMap<String, PackageImpact>,
+
+    #[serde(skip)]
+    affected: HashSet<usize>,
+}
+
+impl ImpactReport {
+    /// Walk the reverse graph from every node matching one of the libraries,
+    /// given by name or by path.
+    pub fn new(g: &BinGraph, libraries: &[String]) -> Result<Self, BingraphError> {
+        let nodes = g.nodes();
+
+        let mut targets: Vec<usize> = vec![];
+        for lib in libraries {
+            let matches = g.find_nodes(lib);
+            if matches.is_empty() {
+                return Err(format!("no node in the graph matches {}", lib).into());
+            }
+            targets.extend(matches);
+        }
+
+        let mut dependents: Vec<Vec<usize>> = vec![vec![]; nodes.len()];
+        for e in g.edges() {
+            dependents[e.target_idx()].push(e.source_idx());
+        }
+
+        // Breadth first, so every node is reached at its shortest distance.
+        let mut distance: BTreeMap<usize, u32> = targets.iter().map(|t| (*t, 0)).collect();
+        let mut queue: VecDeque<usize> = targets.iter().copied().collect();
+        while let Some(idx) = queue.pop_front() {
+            let d = distance[&idx];
+            for dep in dependents[idx].iter() {
+                if !distance.contains_key(dep) {
+                    distance.insert(*dep, d + 1);
+                    queue.push_back(*dep);
+                }
+            }
+        }
+
+        let mut direct = AffectedSet::default();
+        let mut transitive = AffectedSet::default();
+        let mut packages: BTreeMap<String, PackageImpact> = BTreeMap::new();
+
+        for (idx, d) in distance.iter().filter(|(_, d)| **d > 0) {
+            let node = &nodes[*idx];
+            let path = node.absolute_path().to_string();
+            let package = packages
+                .entry(
+                    node.package()
+                        .map(|p| p.name().to_string())
+                        .unwrap_or(String::from("<unowned>")),
+                )
+                .or_default();
+
+            if *d == 1 {
+                direct.push(node.node_type(), path.clone());
+                package.direct.push(path);
+            } else {
+                transitive.push(node.node_type(), path.clone());
+                package.transitive.push(path);
+            }
+        }
+
+        // Without any package databases there is nothing to group by.
+        if !nodes.iter().any(|n| n.package().is_some()) {
+            packages.clear();
+        }
+
+        println!(
+            "{} nodes depend directly and {} transitively on {:?}",
+            distance.values().filter(|d| **d == 1).count(),
+            distance.values().filter(|d| **d > 1).count(),
+            libraries
+        );
+
+        Ok(Self {
+            targets: targets
+                .iter()
+                .map(|t| nodes[*t].absolute_path().to_string())
+                .collect(),
+            direct,
+            transitive,
+            packages,
+            affected: distance.into_keys().collect(),
+        })
+    }
+
+    /// Indices of the targets and of every node affected by them.
+    pub fn affected_nodes(&self) -> &HashSet<usize> {
+        &self.affected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{BinNode, Dependency, EdgeType};
+
+    #[test]
+    fn walks_transitive_dependents_only() {
+        // web.service -> exe -> liba -> libb, other -> libc
+        let nodes = [
+            ("web.service", NodeType::Service, "exe", EdgeType::Exec),
+            ("exe", NodeType::ELFBinary, "liba", EdgeType::Needed),
+            ("liba", NodeType::ELFLibrary, "libb", EdgeType::Needed),
+            ("libb", NodeType::ELFLibrary, "", EdgeType::Needed),
+            ("other", NodeType::ELFBinary, "libc", EdgeType::Needed),
+            ("libc", NodeType::ELFLibrary, "", EdgeType::Needed),
+        ]
+        .into_iter()
+        .map(|(name, t, dep, kind)| {
+            let mut node = BinNode::new(name.to_string(), format!("/{}", name), t);
+            if !dep.is_empty() {
+                node.set_dependencies(vec![Dependency::new(dep, kind)]);
+            }
+            node
+        })
+        .collect();
+        let g = BinGraph::from_nodes(nodes).unwrap();
+
+        let report = ImpactReport::new(&g, &["libb".to_string()]).unwrap();
+        assert_eq!(report.targets, ["/libb"]);
+        assert_eq!(report.direct.libraries, ["/liba"]);
+        assert!(report.direct.executables.is_empty());
+        assert_eq!(report.transitive.executables, ["/exe"]);
+        assert_eq!(report.transitive.services, ["/web.service"]);
+        assert!(report.transitive.libraries.is_empty());
+        assert_eq!(report.affected_nodes(), &HashSet::from([0, 1, 2, 3]));
+
+        // Nothing is owned by a package, so nothing is grouped.
+        assert!(report.packages.is_empty());
+
+        assert!(ImpactReport::new(&g, &["libz".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::collections::HashSet;
+
+use rustworkx_core::petgraph::graph::DiGraph;
+use serde::Serialize;
+
+use crate::{
+    errors::BingraphError,
+    graph::BinGraph,
+    node::{BinNode, EdgeType},
+    scc::Components,
+};
+
+/// Compute the initialization layer of every node over the load-time edges.
+/// Leaves are in layer zero and every other node is one layer above the
+/// highest of its dependencies.
+pub fn layers(graph: &DiGraph<BinNode, EdgeType>) -> Vec<u32> {
+    println!("computing initialization layers for graph");
+    let loads = graph.filter_map(|_, _| Some(()), |_, e| e.is_load().then_some(()));
+    let components = Components::compute(&loads);
+
+    let mut component_layers: Vec<u32> = Vec::with_capacity(components.members.len());
+    for (c, members) in components.members.iter().enumerate() {
+        let layer = members
+            .iter()
+            .flat_map(|idx| loads.neighbors(*idx))
+            .map(|succ| components.component[succ.index()])
+            .filter(|sc| *sc != c)
+            .map(|sc| component_layers[sc] + 1)
+            .max()
+            .unwrap_or(0);
+        component_layers.push(layer);
+    }
+
+    components
+        .component
+        .iter()
+        .map(|c| component_layers[*c])
+        .collect()
+}
+
+/// The simulated startup of a single executable.
+#[derive(Debug, Serialize)]
+pub struct ExecutableInit {
+    executable: String,
+
+    /// Objects in the order they are mapped, breadth first.
+    load_order: Vec<String>,
+
+    /// Objects in the order their constructors run.
+    init_order: Vec<String>,
+}
+
+/// An InitOrderReport simulates the dynamic loader starting each executable.
+#[derive(Debug, Serialize)]
+pub struct InitOrderReport {
+    executables: Vec<ExecutableInit>,
+
+    /// The indices of every node loaded by a simulated executable.
+    #[serde(skip)]
+    loaded: HashSet<usize>,
+}
+
+/// Append `idx` and then every dependency it reaches after its unvisited
+/// dependencies, in the order they are declared.
+fn visit(deps: &[Vec<usize>], idx: usize, visited: &mut [bool], order: &mut Vec<usize>) {
+    visited[idx] = true;
+    for d in deps[idx].iter() {
+        if !visited[*d] {
+            visit(deps, *d, visited, order);
+        }
+    }
+    order.push(idx);
+}
+
+impl InitOrderReport {
+    /// Simulate the executables matching the queries, or every executable in
+    /// the graph when none are given.
+    pub fn new(g: &BinGraph, queries: &[String]) -> Result<Self, BingraphError> {
+        let nodes = g.nodes();
+
+        let mut roots: Vec<usize> = vec![];
+        for q in queries {
+            let matches = g.find_nodes(q);
+            if matches.is_empty() {
+                return Err(format!("no node in the graph matches {}", q).into());
+            }
+            roots.extend(matches);
+        }
+        if queries.is_empty() {
+            roots = (0..nodes.len())
+                .filter(|i| nodes[*i].node_type().is_executable())
+                .collect();
+        }
+
+        // Edges are in the order each node declares its dependencies.
+        let mut deps: Vec<Vec<usize>> = vec![vec![]; nodes.len()];
+        for e in g.edges().iter().filter(|e| e.kind().is_load()) {
+            if !deps[e.source_idx()].contains(&e.target_idx()) {
+                deps[e.source_idx()].push(e.target_idx());
+            }
+        }
+
+        let path = |i: &usize| nodes[*i].absolute_path().to_string();
+        let mut executables: Vec<ExecutableInit> = vec![];
+        let mut visited = vec![false; nodes.len()];
+        let mut loaded: HashSet<usize> = HashSet::new();
+
+        for root in roots {
+            // The loader maps dependencies breadth first, each object once.
+            let mut load_order: Vec<usize> = vec![root];
+            let mut i = 0;
+            while i < load_order.len() {
+                for d in deps[load_order[i]].iter() {
+                    if !load_order.contains(d) {
+                        load_order.push(*d);
+                    }
+                }
+                i += 1;
+            }
+
+            // As in glibc's depth first _dl_sort_maps, objects are visited in
+            // reverse load order and initialized once all of their unvisited
+            // dependencies are. A cycle is broken where it was first entered.
+            // The executable itself is left out of the sort and runs last.
+            let mut init_order: Vec<usize> = vec![];
+            visited[root] = true;
+            for idx in load_order.iter().skip(1).rev() {
+                if !visited[*idx] {
+                    visit(&deps, *idx, &mut visited, &mut init_order);
+                }
+            }
+            init_order.push(root);
+
+            for idx in load_order.iter() {
+                visited[*idx] = false;
+            }
+            loaded.extend(load_order.iter());
+
+            executables.push(ExecutableInit {
+                executable: path(&root),
+                load_order: load_order.iter().map(path).collect(),
+                init_order: init_order.iter().map(path).collect(),
+            });
+        }
+
+        println!("simulated startup of {} executables", executables.len());
+        Ok(Self {
+            executables,
+            loaded,
+        })
+    }
+
+    pub fn loaded_nodes(&self) -> &HashSet<usize> {
+        &self.loaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{Dependency, NodeType};
+
+    fn graph(nodes: &[(&str, NodeType, &[&str])]) -> BinGraph {
+        let nodes = nodes
+            .iter()
+            .map(|(name, t, deps)| {
+                let mut node = BinNode::new(name.to_string(), format!("/{}", name), t.clone());
+                node.set_dependencies(
+                    deps.iter()
+                        .map(|d| Dependency::new(d, EdgeType::Needed))
+                        .collect(),
+                );
+                node
+            })
+            .collect();
+        BinGraph::from_nodes(nodes).unwrap()
+    }
+
+    #[test]
+    fn orders_loads_breadth_first_and_inits_depth_first() {
+        let g = graph(&[
+            ("exe", NodeType::ELFBinary, &["a", "b"]),
+            ("a", NodeType::ELFLibrary, &["c"]),
+            ("b", NodeType::ELFLibrary, &["c"]),
+            ("c", NodeType::ELFLibrary, &[]),
+        ]);
+
+        let report = InitOrderReport::new(&g, &["exe".to_string()]).unwrap();
+        let init = &report.executables[0];
+        assert_eq!(init.load_order, ["/exe", "/a", "/b", "/c"]);
+        assert_eq!(init.init_order, ["/c", "/b", "/a", "/exe"]);
+    }
+
+    #[test]
+    fn breaks_cycles_where_first_entered() {
+        let g = graph(&[
+            ("exe", NodeType::ELFBinary, &["a"]),
+            ("a", NodeType::ELFLibrary, &["b"]),
+            ("b", NodeType::ELFLibrary, &["a"]),
+        ]);
+
+        let report = InitOrderReport::new(&g, &[]).unwrap();
+        assert_eq!(report.executables.len(), 1);
+        assert_eq!(report.executables[0].init_order, ["/a", "/b", "/exe"]);
+    }
+
+    #[test]
+    fn layers_over_load_edges() {
+        // exe -> a <-> b -> c, with exe -> c only a dlopen.
+        let mut graph: DiGraph<BinNode, EdgeType> = DiGraph::new();
+        let mut add = |name: &str| {
+            graph.add_node(BinNode::new(
+                name.to_string(),
+                format!("/{}", name),
+                NodeType::ELFLibrary,
+            ))
+        };
+        let (exe, a, b, c) = (add("exe"), add("a"), add("b"), add("c"));
+        graph.add_edge(exe, a, EdgeType::Needed);
+        graph.add_edge(a, b, EdgeType::Needed);
+        graph.add_edge(b, a, EdgeType::Needed);
+        graph.add_edge(b, c, EdgeType::Needed);
+        graph.add_edge(exe, c, EdgeType::Dlopen);
+
+        // Members of the cycle share a layer.
+        assert_eq!(layers(&graph), [2, 1, 1, 0]);
+    }
+
+    #[test]
+    fn draws_loaded_nodes_by_layer() {
+        let g = graph(&[
+            ("exe", NodeType::ELFBinary, &["a", "c"]),
+            ("a", NodeType::ELFLibrary, &["c"]),
+            ("c", NodeType::ELFLibrary, &[]),
+            ("other", NodeType::ELFBinary, &["d"]),
+            ("d", NodeType::ELFLibrary, &[]),
+        ]);
+
+        let report = InitOrderReport::new(&g, &["exe".to_string()]).unwrap();
+        let loaded = report.loaded_nodes();
+        assert_eq!(loaded.len(), 3);
+
+        let dot = g.serialize_graphviz_layers_of(|i| loaded.contains(&i));
+        let layer = |n: u32| {
+            let start = dot.find(&format!("subgraph layer_{} {{", n)).unwrap();
+            let end = start + dot[start..].find("  }").unwrap();
+            &dot[start..end]
+        };
+        assert!(layer(0).contains("rank=same") && layer(0).contains("\"c\""));
+        assert!(layer(1).contains("\"a\""));
+        assert!(layer(2).contains("\"exe\""));
+        assert!(dot.contains("\"exe\" -> \"a\""));
+        assert!(!dot.contains("layer_3"));
+        assert!(!dot.contains("\"d\"") && !dot.contains("\"other\""));
+    }
+}
@@ -0,0 +1,648 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{
+    io::{Cursor, Read},
+    path::Path,
+};
+
+use serde::Serialize;
+use zip::ZipArchive;
+
+use crate::{
+    errors::BingraphError,
+    node::{BinNode, Dependency, EdgeType, NodeType},
+    pathiter::normalize,
+};
+
+const CLASS_MAGIC: [u8; 4] = [0xca, 0xfe, 0xba, 0xbe];
+
+// The opcodes involved in loading a native library by a constant name.
+const LDC: u8 = 0x12;
+const LDC_W: u8 = 0x13;
+const INVOKEVIRTUAL: u8 = 0xb6;
+const INVOKESTATIC: u8 = 0xb8;
+
+/// Returns whether the provided path looks like a Java archive.
+pub fn is_jar_path(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some("jar")
+}
+
+/// JarInfo holds the parts of a Java archive relevant to its dependencies.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct JarInfo {
+    module_name: String,
+    main_class: Option<String>,
+    class_path: Vec<String>,
+    requires: Vec<String>,
+
+    /// Native libraries shipped inside the archive.
+    bundled_libraries: Vec<String>,
+
+    /// Library names passed to `System.loadLibrary`.
+    loaded_libraries: Vec<String>,
+}
+
+impl JarInfo {
+    pub fn module_name(&self) -> &str {
+        &self.module_name
+    }
+}
+
+/// Parse the main section of a manifest into its key/value pairs, joining
+/// continuation lines (which start with a single space) onto their key.
+fn manifest_attributes(manifest: &str) -> Vec<(String, String)> {
+    let mut attrs: Vec<(String, String)> = vec![];
+
+    for line in manifest.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            // The main section ends at the first blank line.
+            break;
+        }
+
+        if let Some(cont) = line.strip_prefix(' ') {
+            if let Some((_, v)) = attrs.last_mut() {
+                v.push_str(cont);
+            }
+        } else if let Some((k, v)) = line.split_once(':') {
+            attrs.push((k.trim().to_string(), v.trim().to_string()));
+        }
+    }
+
+    attrs
+}
+
+/// Derive the name of an automatic module from the archive file name, as the
+/// module system does when no `Automatic-Module-Name` is given.
+fn automatic_module_name(file_name: &str) -> String {
+    let stem = file_name.strip_suffix(".jar").unwrap_or(file_name);
+
+    // Drop any version suffix, which starts at a dash followed by a digit.
+    let stem = stem
+        .char_indices()
+        .find(|(i, c)| {
+            *c == '-'
+                && stem[i + 1..]
+                    .chars()
+                    .next()
+                    .is_some_and(|n| n.is_ascii_digit())
+        })
+        .map(|(i, _)| &stem[..i])
+        .unwrap_or(stem);
+
+    stem.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join(".")
+}
+
+/// Length of the JVM instruction at `pc`, including its opcode, or None past
+/// the end of the code.
+fn instruction_length(code: &[u8], pc: usize) -> Option<usize> {
+    // Switch operands are aligned to four bytes from the start of the code.
+    let pad = (4 - (pc + 1) % 4) % 4;
+    let operands = pc + 1 + pad;
+
+    Some(match *code.get(pc)? {
+        0x10 | 0x12 | 0x15..=0x19 | 0x36..=0x3a | 0xa9 | 0xbc => 2,
+        0x11
+        | 0x13
+        | 0x14
+        | 0x84
+        | 0x99..=0xa8
+        | 0xb2..=0xb8
+        | 0xbb
+        | 0xbd
+        | 0xc0
+        | 0xc1
+        | 0xc6
+        | 0xc7 => 3,
+        0xc5 => 4,
+        0xb9 | 0xba | 0xc8 | 0xc9 => 5,
+        // tableswitch: default, low and high, then a jump per case.
+        0xaa => {
+            let low = u32_at(code, operands + 4)? as i32;
+            let high = u32_at(code, operands + 8)? as i32;
+            let cases = usize::try_from(i64::from(high) - i64::from(low) + 1).ok()?;
+            1 + pad + 12 + 4 * cases
+        }
+        // lookupswitch: default and a count, then match and jump pairs.
+        0xab => 1 + pad + 8 + 8 * u32_at(code, operands + 4)? as usize,
+        // wide widens the index of the next instruction, and iinc's constant.
+        0xc4 => {
+            if *code.get(pc + 1)? == 0x84 {
+                6
+            } else {
+                4
+            }
+        }
+        _ => 1,
+    })
+}
+
+/// A minimal reader over the constant pool of a class file.
+struct ClassFile {
+    utf8: Vec<Option<String>>,
+
+    /// For each Class, Module and Package slot, the index of its name.
+    refs: Vec<u16>,
+
+    /// For each String slot, the index of its Utf8 contents.
+    strings: Vec<u16>,
+
+    /// For each Methodref and InterfaceMethodref slot, the index of its class
+    /// and of its NameAndType.
+    methods: Vec<(u16, u16)>,
+
+    /// For each NameAndType slot, the index of its name.
+    names: Vec<u16>,
+
+    /// Remaining bytes after the constant pool.
+    rest: Vec<u8>,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+    ]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+impl ClassFile {
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.get(0..4)? != CLASS_MAGIC {
+            return None;
+        }
+
+        let count = u16_at(data, 8)? as usize;
+        let mut class = Self {
+            utf8: vec![None; count],
+            refs: vec![0; count],
+            strings: vec![0; count],
+            methods: vec![(0, 0); count],
+            names: vec![0; count],
+            rest: vec![],
+        };
+
+        let mut offset = 10;
+        let mut i = 1;
+        while i < count {
+            let tag = *data.get(offset)?;
+            offset += 1;
+            match tag {
+                1 => {
+                    let len = u16_at(data, offset)? as usize;
+                    let bytes = data.get(offset + 2..offset + 2 + len)?;
+                    class.utf8[i] = Some(String::from_utf8_lossy(bytes).to_string());
+                    offset += 2 + len;
+                }
+                7 | 19 | 20 => {
+                    class.refs[i] = u16_at(data, offset)?;
+                    offset += 2;
+                }
+                8 => {
+                    class.strings[i] = u16_at(data, offset)?;
+                    offset += 2;
+                }
+                10 | 11 => {
+                    class.methods[i] = (u16_at(data, offset)?, u16_at(data, offset + 2)?);
+                    offset += 4;
+                }
+                12 => {
+                    class.names[i] = u16_at(data, offset)?;
+                    offset += 4;
+                }
+                16 => offset += 2,
+                15 => offset += 3,
+                3 | 4 | 9 | 17 | 18 => offset += 4,
+                5 | 6 => {
+                    offset += 8;
+                    // Longs and doubles take up two slots.
+                    i += 1;
+                }
+                _ => return None,
+            }
+            i += 1;
+        }
+
+        class.rest = data.get(offset..)?.to_vec();
+        Some(class)
+    }
+
+    fn utf8(&self, idx: u16) -> Option<&str> {
+        self.utf8.get(idx as usize)?.as_deref()
+    }
+
+    /// Resolve a Class or Module entry to its name.
+    fn ref_name(&self, idx: u16) -> Option<&str> {
+        self.utf8(*self.refs.get(idx as usize)?)
+    }
+
+    fn has_utf8(&self, s: &str) -> bool {
+        self.utf8.iter().any(|u| u.as_deref() == Some(s))
+    }
+
+    /// Resolve a Methodref to its class and method name.
+    fn method_ref(&self, idx: u16) -> Option<(&str, &str)> {
+        let (class, name_and_type) = *self.methods.get(idx as usize)?;
+        let name = self.utf8(*self.names.get(name_and_type as usize)?)?;
+        Some((self.ref_name(class)?, name))
+    }
+
+    /// Skip a fields or methods table starting at `offset` within the bytes
+    /// following the constant pool, returning the offset after it.
+    fn skip_members(&self, mut offset: usize) -> Option<usize> {
+        let data = &self.rest;
+        let count = u16_at(data, offset)?;
+        offset += 2;
+        for _ in 0..count {
+            offset += 6;
+            let attrs = u16_at(data, offset)?;
+            offset += 2;
+            for _ in 0..attrs {
+                offset += 6 + u32_at(data, offset + 2)? as usize;
+            }
+        }
+        Some(offset)
+    }
+
+    /// Offset of the methods table, after the access flags, this and super
+    /// class, the interfaces and the fields.
+    fn methods_offset(&self) -> Option<usize> {
+        let offset = 6;
+        self.skip_members(offset + 2 + 2 * u16_at(&self.rest, offset)? as usize)
+    }
+
+    /// Return the bytecode of every method that has any.
+    fn method_code(&self) -> Option<Vec<&[u8]>> {
+        let data = &self.rest;
+        let mut offset = self.methods_offset()?;
+        let mut code: Vec<&[u8]> = vec![];
+
+        let count = u16_at(data, offset)?;
+        offset += 2;
+        for _ in 0..count {
+            let attrs = u16_at(data, offset + 6)?;
+            offset += 8;
+            for _ in 0..attrs {
+                let name = self.utf8(u16_at(data, offset)?)?;
+                let len = u32_at(data, offset + 2)? as usize;
+
+                // Code starts with the maximum stack and locals, then the
+                // length of the bytecode.
+                if name == "Code" {
+                    let code_len = u32_at(data, offset + 10)? as usize;
+                    code.push(data.get(offset + 14..offset + 14 + code_len)?);
+                }
+                offset += 6 + len;
+            }
+        }
+
+        Some(code)
+    }
+
+    /// Library names this class passes to `System.loadLibrary` or
+    /// `Runtime.loadLibrary` as a constant, that is where the call directly
+    /// follows an `ldc` of a string.
+    fn loaded_libraries(&self) -> Vec<String> {
+        if !self.has_utf8("loadLibrary") {
+            return vec![];
+        }
+
+        let mut libraries: Vec<String> = vec![];
+        for code in self.method_code().unwrap_or_default() {
+            let mut pc = 0;
+            let mut constant: Option<u16> = None;
+            while let Some(len) = instruction_length(code, pc) {
+                let op = code[pc];
+                let callee = match op {
+                    INVOKESTATIC => Some(("java/lang/System", "loadLibrary")),
+                    INVOKEVIRTUAL => Some(("java/lang/Runtime", "loadLibrary")),
+                    _ => None,
+                };
+
+                if let (Some(callee), Some(c)) = (callee, constant) {
+                    if u16_at(code, pc + 1).and_then(|m| self.method_ref(m)) == Some(callee) {
+                        libraries.extend(self.utf8(self.strings[c as usize]).map(String::from));
+                    }
+                }
+
+                // Remember a string constant just pushed for the next instruction.
+                constant = match op {
+                    LDC => code.get(pc + 1).map(|i| *i as u16),
+                    LDC_W => u16_at(code, pc + 1),
+                    _ => None,
+                }
+                .filter(|i| self.strings.get(*i as usize).is_some_and(|s| *s != 0));
+                pc += len;
+            }
+        }
+
+        libraries
+    }
+
+    /// Parse the name and required modules out of the Module attribute of a
+    /// `module-info.class`.
+    fn module(&self) -> Option<(String, Vec<String>)> {
+        let data = &self.rest;
+        let mut offset = self.skip_members(self.methods_offset()?)?;
+
+        let attrs = u16_at(data, offset)?;
+        offset += 2;
+        for _ in 0..attrs {
+            let name = self.utf8(u16_at(data, offset)?)?;
+            let len = u32_at(data, offset + 2)? as usize;
+            offset += 6;
+
+            if name == "Module" {
+                let module = self.ref_name(u16_at(data, offset)?)?.to_string();
+                let count = u16_at(data, offset + 6)? as usize;
+
+                let requires = (0..count)
+                    .filter_map(|r| self.ref_name(u16_at(data, offset + 8 + r * 6)?))
+                    .map(String::from)
+                    .collect();
+                return Some((module, requires));
+            }
+
+            offset += len;
+        }
+
+        None
+    }
+}
+
+/// Create a node from a Java archive.
+pub fn node(name: String, absolute_path: String, data: &[u8]) -> Result<BinNode, BingraphError> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let mut info = JarInfo::default();
+    let mut explicit_module = false;
+    let mut automatic_name: Option<String> = None;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let entry_name = entry.name().to_string();
+
+        if entry_name.ends_with(".so") {
+            let file_name = entry_name.rsplit('/').next().unwrap_or(&entry_name);
+            info.bundled_libraries.push(file_name.to_string());
+            continue;
+        }
+
+        let is_manifest = entry_name == "META-INF/MANIFEST.MF";
+        if !is_manifest && !entry_name.ends_with(".class") {
+            continue;
+        }
+
+        let mut contents: Vec<u8> = vec![];
+        entry.read_to_end(&mut contents)?;
+
+        if is_manifest {
+            for (k, v) in manifest_attributes(&String::from_utf8_lossy(&contents)) {
+                match k.as_str() {
+                    "Class-Path" => info
+                        .class_path
+                        .extend(v.split_whitespace().map(String::from)),
+                    "Main-Class" => info.main_class = Some(v),
+                    "Automatic-Module-Name" => automatic_name = Some(v),
+                    _ => {}
+                }
+            }
+            continue;
+        }
+
+        let Some(class) = ClassFile::parse(&contents) else {
+            continue;
+        };
+
+        if entry_name.ends_with("module-info.class") {
+            if let Some((module, requires)) = class.module() {
+                info.module_name = module;
+                info.requires = requires;
+                explicit_module = true;
+            }
+        } else {
+            info.loaded_libraries.extend(class.loaded_libraries());
+        }
+    }
+
+    if !explicit_module {
+        info.module_name = automatic_name.unwrap_or_else(|| automatic_module_name(&name));
+    }
+
+    info.bundled_libraries.sort();
+    info.bundled_libraries.dedup();
+    info.loaded_libraries.sort();
+    info.loaded_libraries.dedup();
+
+    let dir = Path::new(&absolute_path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let mut deps: Vec<Dependency> = vec![];
+    for cp in info.class_path.iter() {
+        let path = normalize(&dir.join(cp));
+        deps.push(Dependency::new(cp, EdgeType::ClassPath).with_paths(vec![path]));
+    }
+    for module in info.requires.iter() {
+        deps.push(Dependency::new(module, EdgeType::Requires));
+    }
+    for lib in info.bundled_libraries.iter() {
+        deps.push(Dependency::new(lib, EdgeType::Jni));
+    }
+    for lib in info.loaded_libraries.iter() {
+        deps.push(Dependency::new(&format!("lib{}.so", lib), EdgeType::Jni));
+    }
+
+    let t = if explicit_module {
+        NodeType::JavaModule
+    } else {
+        NodeType::JavaArchive
+    };
+
+    let mut node = BinNode::new(name, absolute_path, t);
+    node.set_dependencies(deps);
+    node.set_jar_info(info);
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a class file, one constant pool entry at a time.
+    #[derive(Default)]
+    struct Builder {
+        pool: Vec<u8>,
+        count: u16,
+    }
+
+    impl Builder {
+        fn entry(&mut self, bytes: &[u8]) -> u16 {
+            self.pool.extend(bytes);
+            self.count += 1;
+            self.count
+        }
+
+        fn utf8(&mut self, s: &str) -> u16 {
+            let mut bytes = vec![1];
+            bytes.extend((s.len() as u16).to_be_bytes());
+            bytes.extend(s.as_bytes());
+            self.entry(&bytes)
+        }
+
+        fn with_index(&mut self, tag: u8, idx: u16) -> u16 {
+            let [a, b] = idx.to_be_bytes();
+            self.entry(&[tag, a, b])
+        }
+
+        fn string(&mut self, s: &str) -> u16 {
+            let utf8 = self.utf8(s);
+            self.with_index(8, utf8)
+        }
+
+        fn method(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+            let class_name = self.utf8(class);
+            let class = self.with_index(7, class_name);
+            let (name, descriptor) = (self.utf8(name), self.utf8(descriptor));
+            let mut nat = vec![12];
+            nat.extend(name.to_be_bytes());
+            nat.extend(descriptor.to_be_bytes());
+            let nat = self.entry(&nat);
+
+            let mut method = vec![10];
+            method.extend(class.to_be_bytes());
+            method.extend(nat.to_be_bytes());
+            self.entry(&method)
+        }
+
+        /// Finish the class with a single method holding the bytecode.
+        fn finish(mut self, code: &[u8]) -> Vec<u8> {
+            let code_name = self.utf8("Code");
+            let name = self.utf8("run");
+
+            let mut out = CLASS_MAGIC.to_vec();
+            out.extend([0, 0, 0, 52]);
+            out.extend((self.count + 1).to_be_bytes());
+            out.extend(&self.pool);
+            // Access flags, this and super class, no interfaces or fields.
+            out.extend([0; 10]);
+
+            out.extend(1u16.to_be_bytes());
+            out.extend([0, 9]);
+            out.extend(name.to_be_bytes());
+            out.extend(name.to_be_bytes());
+            out.extend(1u16.to_be_bytes());
+            out.extend(code_name.to_be_bytes());
+            out.extend((12 + code.len() as u32).to_be_bytes());
+            out.extend([0, 2, 0, 1]);
+            out.extend((code.len() as u32).to_be_bytes());
+            out.extend(code);
+            out.extend([0; 4]);
+
+            // No class attributes.
+            out.extend([0, 0]);
+            out
+        }
+    }
+
+    #[test]
+    fn only_constants_passed_to_load_library() {
+        let mut b = Builder::default();
+        let native = b.string("native");
+        let other = b.string("unrelated");
+        let wide = b.string("wide");
+        let load = b.method("java/lang/System", "loadLibrary", "(Ljava/lang/String;)V");
+        let print = b.method("java/io/PrintStream", "println", "(Ljava/lang/String;)V");
+        let runtime = b.method("java/lang/Runtime", "getRuntime", "()Ljava/lang/Runtime;");
+        let rt_load = b.method("java/lang/Runtime", "loadLibrary", "(Ljava/lang/String;)V");
+
+        let mut code: Vec<u8> = vec![];
+        code.extend([LDC, native as u8, INVOKESTATIC]);
+        code.extend(load.to_be_bytes());
+        code.extend([LDC, other as u8, INVOKEVIRTUAL]);
+        code.extend(print.to_be_bytes());
+        // An ldc that does not directly feed the call is not taken.
+        code.extend([LDC, other as u8, 0x4c, INVOKESTATIC]);
+        code.extend(load.to_be_bytes());
+        // A tableswitch, padded to four bytes, with two cases.
+        code.push(0xaa);
+        code.resize(code.len().next_multiple_of(4), 0);
+        code.extend([
+            0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 20, 0, 0, 0, 20,
+        ]);
+        code.push(INVOKESTATIC);
+        code.extend(runtime.to_be_bytes());
+        code.push(LDC_W);
+        code.extend(wide.to_be_bytes());
+        code.push(INVOKEVIRTUAL);
+        code.extend(rt_load.to_be_bytes());
+        code.push(0xb1);
+
+        let class = ClassFile::parse(&b.finish(&code)).unwrap();
+        assert_eq!(class.loaded_libraries(), vec!["native", "wide"]);
+    }
+
+    #[test]
+    fn classes_without_load_library() {
+        let mut b = Builder::default();
+        let s = b.string("native");
+        let class = ClassFile::parse(&b.finish(&[LDC, s as u8, 0x57, 0xb1])).unwrap();
+        assert!(class.loaded_libraries().is_empty());
+        assert!(ClassFile::parse(b"not a class").is_none());
+    }
+
+    #[test]
+    fn instruction_lengths() {
+        assert_eq!(instruction_length(&[0x10, 1], 0), Some(2));
+        assert_eq!(instruction_length(&[0xb9, 0, 1, 1, 0], 0), Some(5));
+        assert_eq!(instruction_length(&[0xc4, 0x84, 0, 1, 0, 1], 0), Some(6));
+        assert_eq!(instruction_length(&[0xc4, 0x15, 0, 1], 0), Some(4));
+        // lookupswitch at offset 1 needs two bytes of padding.
+        let mut code = vec![0x00, 0xab, 0, 0];
+        code.extend([0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0, 9]);
+        assert_eq!(instruction_length(&code, 1), Some(1 + 2 + 8 + 8));
+        assert_eq!(instruction_length(&code, code.len()), None);
+    }
+
+    #[test]
+    fn manifest_and_automatic_names() {
+        let attrs = manifest_attributes(
+            "Manifest-Version: 1.0\r\nClass-Path: lib/a.jar\r\n  lib/b.jar\r\n\r\nName: x\r\n",
+        );
+        assert_eq!(
+            attrs,
+            vec![
+                ("Manifest-Version".to_string(), "1.0".to_string()),
+                ("Class-Path".to_string(), "lib/a.jar lib/b.jar".to_string()),
+            ]
+        );
+        assert_eq!(
+            automatic_module_name("commons-lang3-3.12.0.jar"),
+            "commons.lang3"
+        );
+    }
+}
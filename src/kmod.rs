@@ -0,0 +1,349 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Read,
+    path::Path,
+};
+
+use goblin::elf::Elf;
+use serde::Serialize;
+
+use crate::{
+    errors::BingraphError,
+    graph::BinGraph,
+    node::{BinNode, Dependency, EdgeType},
+    pathiter::PathIterator,
+};
+
+/// File suffixes that kernel modules are installed with, plain or compressed.
+const MODULE_SUFFIXES: [&str; 4] = [".ko", ".ko.xz", ".ko.zst", ".ko.gz"];
+
+/// Returns whether the provided path looks like a (possibly compressed) kernel module.
+pub fn is_module_path(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    MODULE_SUFFIXES.iter().any(|s| name.ends_with(s))
+}
+
+/// Strip the module suffix from a file name and normalize it the same way
+/// the kernel does, so that `snd-hda-intel.ko.zst` becomes `snd_hda_intel`.
+fn module_name_from_file(name: &str) -> String {
+    let stem = MODULE_SUFFIXES
+        .iter()
+        .rev()
+        .find_map(|s| name.strip_suffix(s))
+        .unwrap_or(name);
+    normalize_name(stem)
+}
+
+/// Kernel module names are interchangeable between dashes and underscores.
+fn normalize_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// Read a kernel module from disk, decompressing it if required.
+pub fn read_module(path: &Path) -> Result<Vec<u8>, BingraphError> {
+    let file = fs::read(path)?;
+    let name = path.to_string_lossy();
+
+    let mut data: Vec<u8> = vec![];
+    if name.ends_with(".xz") {
+        xz2::read::XzDecoder::new(file.as_slice()).read_to_end(&mut data)?;
+    } else if name.ends_with(".gz") {
+        flate2::read::GzDecoder::new(file.as_slice()).read_to_end(&mut data)?;
+    } else if name.ends_with(".zst") {
+        data = zstd::stream::decode_all(file.as_slice())?;
+    } else {
+        data = file;
+    }
+
+    Ok(data)
+}
+
+/// ModuleInfo holds the fields of a kernel module's `.modinfo` section that
+/// are relevant for building a dependency graph.
+#[derive(Debug, Serialize, Clone)]
+pub struct ModuleInfo {
+    name: String,
+    vermagic: Option<String>,
+    depends: Vec<String>,
+    softdeps: Vec<String>,
+}
+
+impl ModuleInfo {
+    /// Parse the `.modinfo` section out of an ELF object, returning None if
+    /// the object has no such section and is therefore not a kernel module.
+    pub fn parse(elf: &Elf, data: &[u8], file_name: &str) -> Option<Self> {
+        let section = elf
+            .section_headers
+            .iter()
+            .find(|sh| elf.shdr_strtab.get_at(sh.sh_name) == Some(".modinfo"))?;
+
+        let start = section.sh_offset as usize;
+        let end = start.checked_add(section.sh_size as usize)?;
+        let modinfo = data.get(start..end)?;
+
+        Some(Self::from_modinfo(modinfo, file_name))
+    }
+
+    /// Parse the contents of a `.modinfo` section, a sequence of NUL
+    /// terminated key=value strings.
+    fn from_modinfo(modinfo: &[u8], file_name: &str) -> Self {
+        let mut info = Self {
+            name: module_name_from_file(file_name),
+            vermagic: None,
+            depends: vec![],
+            softdeps: vec![],
+        };
+
+        for entry in modinfo.split(|b| *b == 0) {
+            let entry = String::from_utf8_lossy(entry);
+            let Some((key, value)) = entry.split_once('=') else {
+                continue;
+            };
+
+            match key {
+                "name" => info.name = normalize_name(value),
+                "vermagic" => info.vermagic = Some(value.to_string()),
+                "depends" => info.depends.extend(
+                    value
+                        .split(',')
+                        .filter(|d| !d.is_empty())
+                        .map(normalize_name),
+                ),
+                // softdep values look like "pre: mod_a mod_b post: mod_c".
+                "softdep" => info.softdeps.extend(
+                    value
+                        .split_whitespace()
+                        .filter(|d| !d.ends_with(':'))
+                        .map(normalize_name),
+                ),
+                _ => {}
+            }
+        }
+
+        info
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the hard and soft dependencies of this module as typed dependencies.
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        let hard = self
+            .depends
+            .iter()
+            .map(|d| Dependency::new(d, EdgeType::Needed));
+        let soft = self
+            .softdeps
+            .iter()
+            .map(|d| Dependency::new(d, EdgeType::SoftDep));
+        hard.chain(soft).collect()
+    }
+}
+
+/// Resolve the directory holding the modules for a kernel release, defaulting
+/// to the release of the running kernel.
+pub fn release_dir(modules_dir: &str, release: Option<String>) -> Result<String, BingraphError> {
+    let release = match release {
+        Some(r) => r,
+        None => fs::read_to_string("/proc/sys/kernel/osrelease")?
+            .trim()
+            .to_string(),
+    };
+
+    Ok(format!("{}/{}", modules_dir.trim_end_matches('/'), release))
+}
+
+/// Walk a kernel release directory and create a node for every kernel module found.
+pub fn scan(release_dir: &str) -> Vec<BinNode> {
+    println!("searching through {} for kernel modules", release_dir);
+
+    let mut nodes: Vec<BinNode> = vec![];
+    for entry in PathIterator::recursive(release_dir) {
+        let path = entry.path();
+        if !is_module_path(&path) {
+            continue;
+        }
+
+        match BinNode::from_entry(entry) {
+            Ok(n) => nodes.extend(n),
+            Err(e) => println!("unable to create node at {:?}: {}", path, e),
+        }
+    }
+
+    nodes
+}
+
+/// A ModulesDepMismatch records a module whose `depends=` closure disagrees
+/// with the dependencies depmod wrote into `modules.dep`.
+#[derive(Debug, Serialize)]
+pub struct ModulesDepMismatch {
+    pub module: String,
+    pub missing_from_modules_dep: Vec<String>,
+    pub missing_from_modinfo: Vec<String>,
+}
+
+/// Compare the transitive `depends=` closure of every scanned module against
+/// the contents of `modules.dep` in the release directory.
+pub fn cross_check(
+    release_dir: &str,
+    nodes: &[BinNode],
+) -> Result<Vec<ModulesDepMismatch>, BingraphError> {
+    let contents = fs::read_to_string(format!("{}/modules.dep", release_dir))?;
+
+    let mut modules_dep: HashMap<String, HashSet<String>> = HashMap::new();
+    for line in contents.lines() {
+        let Some((module, deps)) = line.split_once(':') else {
+            continue;
+        };
+
+        let file_name = |p: &str| {
+            module_name_from_file(
+                Path::new(p)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or(p),
+            )
+        };
+
+        modules_dep.insert(
+            file_name(module),
+            deps.split_whitespace().map(file_name).collect(),
+        );
+    }
+
+    let depends: HashMap<String, Vec<String>> = nodes
+        .iter()
+        .map(|n| {
+            let hard = n
+                .get_dependencies()
+                .iter()
+                .filter(|d| d.kind == EdgeType::Needed)
+                .map(|d| d.name.clone())
+                .collect();
+            (n.name(), hard)
+        })
+        .collect();
+
+    let mut mismatches: Vec<ModulesDepMismatch> = vec![];
+    for node in nodes.iter() {
+        let name = node.name();
+
+        // Compute the transitive closure of the hard dependencies.
+        let mut closure: HashSet<String> = HashSet::new();
+        let mut stack: Vec<&String> = depends.get(&name).into_iter().flatten().collect();
+        while let Some(dep) = stack.pop() {
+            if closure.insert(dep.clone()) {
+                stack.extend(depends.get(dep).into_iter().flatten());
+            }
+        }
+
+        let expected = modules_dep.get(&name).cloned().unwrap_or_default();
+        let mut missing_from_modules_dep: Vec<String> =
+            closure.difference(&expected).cloned().collect();
+        let mut missing_from_modinfo: Vec<String> =
+            expected.difference(&closure).cloned().collect();
+
+        if !missing_from_modules_dep.is_empty() || !missing_from_modinfo.is_empty() {
+            missing_from_modules_dep.sort();
+            missing_from_modinfo.sort();
+            mismatches.push(ModulesDepMismatch {
+                module: name,
+                missing_from_modules_dep,
+                missing_from_modinfo,
+            });
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Build the module dependency graph for a kernel release, optionally
+/// reporting any disagreement with `modules.dep`.
+pub fn module_graph(
+    modules_dir: &str,
+    release: Option<String>,
+    check: bool,
+) -> Result<BinGraph, BingraphError> {
+    let dir = release_dir(modules_dir, release)?;
+    let nodes = scan(&dir);
+
+    if check {
+        let mismatches = cross_check(&dir, &nodes)?;
+        for m in mismatches.iter() {
+            println!(
+                "{}: missing from modules.dep: {:?}, missing from modinfo: {:?}",
+                m.module, m.missing_from_modules_dep, m.missing_from_modinfo
+            );
+        }
+        println!(
+            "{} of {} modules disagree with modules.dep",
+            mismatches.len(),
+            nodes.len()
+        );
+    }
+
+    BinGraph::from_nodes(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_names_are_normalized() {
+        assert_eq!(
+            module_name_from_file("snd-hda-intel.ko.zst"),
+            "snd_hda_intel"
+        );
+        assert_eq!(module_name_from_file("ext4.ko"), "ext4");
+        assert_eq!(module_name_from_file("nf_tables.ko.xz"), "nf_tables");
+        assert!(is_module_path(Path::new("/lib/modules/x/btrfs.ko.gz")));
+        assert!(!is_module_path(Path::new("/lib/modules/x/modules.dep")));
+    }
+
+    #[test]
+    fn modinfo_fields() {
+        let modinfo = b"license=GPL\0depends=mbcache,jbd2,crc16\0\0softdep=pre: crc32c post: some-mod\0name=ext4\0vermagic=6.1.0 SMP mod_unload \0";
+        let info = ModuleInfo::from_modinfo(modinfo, "ext4.ko");
+
+        assert_eq!(info.name(), "ext4");
+        assert_eq!(info.vermagic.as_deref(), Some("6.1.0 SMP mod_unload "));
+        assert_eq!(info.depends, vec!["mbcache", "jbd2", "crc16"]);
+        assert_eq!(info.softdeps, vec!["crc32c", "some_mod"]);
+
+        let deps = info.dependencies();
+        assert_eq!(deps.len(), 5);
+        assert_eq!(deps[3].name, "crc32c");
+        assert_eq!(deps[3].kind, EdgeType::SoftDep);
+    }
+
+    #[test]
+    fn modinfo_without_name_uses_file_name() {
+        let info = ModuleInfo::from_modinfo(b"depends=\0", "dm-mod.ko.xz");
+        assert_eq!(info.name(), "dm_mod");
+        assert!(info.depends.is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use crate::{
+    errors::BingraphError,
+    graph::BinGraph,
+    live::canonical,
+    node::{BinNode, Dependency, EdgeType},
+};
+
+/// A Load is a single library load recorded by the dynamic loader.
+#[derive(Debug, Clone)]
+struct Load {
+    /// The path of the object that caused the load, or the bare name the
+    /// loader reports for the main program.
+    loader: String,
+
+    /// The library name as requested.
+    name: String,
+
+    /// The path the library was loaded from, if the trace shows it.
+    path: Option<String>,
+
+    dlopen: bool,
+}
+
+/// The state of the trace of a single process.
+#[derive(Default)]
+struct ProcessTrace {
+    pending: Option<Load>,
+    tried: Option<String>,
+
+    /// Paths of the libraries already loaded, by requested name.
+    loaded: HashMap<String, String>,
+}
+
+impl ProcessTrace {
+    fn flush(&mut self, loads: &mut Vec<Load>) {
+        if let Some(mut load) = self.pending.take() {
+            if load.path.is_none() {
+                load.path = self.loaded.get(&load.name).cloned();
+            }
+            loads.push(load);
+        }
+    }
+}
+
+/// Strip the ` [<namespace>]` suffix the loader appends to object names.
+fn object_name(s: &str) -> &str {
+    s.trim()
+        .rsplit_once(" [")
+        .map(|(n, _)| n)
+        .unwrap_or(s.trim())
+}
+
+/// Parse an `LD_DEBUG=libs,files` log, which may interleave the output of
+/// several processes, into the loads it records.
+fn parse(contents: &str) -> Vec<Load> {
+    let mut traces: HashMap<String, ProcessTrace> = HashMap::new();
+    let mut loads: Vec<Load> = vec![];
+
+    for line in contents.lines() {
+        let Some((pid, msg)) = line.split_once(':') else {
+            continue;
+        };
+        let pid = pid.trim();
+        if pid.is_empty() || !pid.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+
+        let trace = traces.entry(pid.to_string()).or_default();
+        let msg = msg.trim();
+
+        if let Some(tried) = msg.strip_prefix("trying file=") {
+            trace.tried = Some(tried.to_string());
+            continue;
+        }
+
+        let Some((file, event)) = msg.strip_prefix("file=").and_then(|m| m.split_once(';')) else {
+            continue;
+        };
+        let name = object_name(file).to_string();
+        let event = event.trim();
+
+        let loader = event
+            .strip_prefix("needed by ")
+            .map(|l| (l, false))
+            .or_else(|| {
+                event
+                    .strip_prefix("dynamically loaded by ")
+                    .map(|l| (l, true))
+            });
+
+        if let Some((loader, dlopen)) = loader {
+            trace.flush(&mut loads);
+            trace.tried = None;
+            trace.pending = Some(Load {
+                loader: object_name(loader).to_string(),
+                name,
+                path: None,
+                dlopen,
+            });
+        } else if event == "generating link map" {
+            // Names with a slash are loaded from that path, otherwise from
+            // the last candidate the search tried.
+            let path = if name.contains('/') {
+                Some(name.clone())
+            } else {
+                trace.tried.take()
+            };
+            if let Some(ref p) = path {
+                trace.loaded.insert(name.clone(), p.clone());
+            }
+            if let Some(pending) = trace.pending.as_mut().filter(|l| l.name == name) {
+                pending.path = path;
+            }
+            trace.flush(&mut loads);
+        }
+    }
+
+    for trace in traces.values_mut() {
+        trace.flush(&mut loads);
+    }
+
+    loads
+}
+
+/// Build the static graph of the binary and library paths, overlaid with
+/// the loads recorded in `LD_DEBUG=libs,files` logs. Every edge is marked as
+/// observed or not, and libraries loaded with dlopen gain an edge from the
+/// object that loaded them.
+pub fn trace_graph(logs: &[String], path: &str) -> Result<BinGraph, BingraphError> {
+    let mut loads: Vec<Load> = vec![];
+    for log in logs {
+        let contents = fs::read_to_string(log)?;
+        loads.extend(parse(&contents));
+    }
+    println!(
+        "read {} library loads from {} logs",
+        loads.len(),
+        logs.len()
+    );
+
+    let mut nodes = BinGraph::scan(path);
+
+    // Loaded libraries outside of the scanned paths get a node of their own.
+    let mut known: HashSet<String> = nodes.iter().map(|n| canonical(n.absolute_path())).collect();
+    for p in loads.iter().filter_map(|l| l.path.as_ref()) {
+        if known.insert(canonical(p)) && Path::new(p).exists() {
+            match BinNode::from_path(Path::new(p)) {
+                Ok(n) => nodes.extend(n),
+                Err(e) => println!("unable to create node at {:?}: {}", p, e),
+            }
+        }
+    }
+
+    // The main program is reported as it was invoked, so map every loader
+    // onto the same canonical path `mark_observed` keys its node by.
+    let by_name: HashMap<String, String> = nodes
+        .iter()
+        .map(|n| (n.name(), canonical(n.absolute_path())))
+        .collect();
+    let loader_path = |loader: &str| {
+        if loader.starts_with('/') {
+            return canonical(loader);
+        }
+        let file_name = loader.rsplit('/').next().unwrap_or(loader);
+        by_name
+            .get(file_name)
+            .cloned()
+            .unwrap_or(loader.to_string())
+    };
+
+    let mut observed: HashSet<(String, String)> = HashSet::new();
+    let mut dlopens: HashMap<String, HashSet<String>> = HashMap::new();
+    for load in loads.iter() {
+        let Some(ref path) = load.path else {
+            continue;
+        };
+        let (loader, target) = (loader_path(&load.loader), canonical(path));
+        if load.dlopen {
+            dlopens
+                .entry(loader.clone())
+                .or_default()
+                .insert(target.clone());
+        }
+        observed.insert((loader, target));
+    }
+
+    for node in nodes.iter_mut() {
+        let Some(targets) = dlopens.get(&canonical(node.absolute_path())) else {
+            continue;
+        };
+        let deps = targets
+            .iter()
+            .map(|t| Dependency::new(t, EdgeType::Dlopen).with_paths(vec![t.clone()]))
+            .collect();
+        node.add_dependencies(deps);
+    }
+
+    let used: HashSet<&String> = observed.iter().flat_map(|(l, t)| [l, t]).collect();
+
+    let mut g = BinGraph::from_nodes(nodes)?;
+    g.mark_observed(
+        |p| used.contains(&p.to_string()),
+        |s, t| observed.contains(&(s.to_string(), t.to_string())),
+    );
+    Ok(g)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+      4242:\t
+      4242:\tfile=libc.so.6 [0];  needed by ./ls [0]
+      4243:\tfile=libz.so.1 [0];  needed by /usr/bin/gzip [0]
+      4242:\tfind library=libc.so.6 [0]; searching
+      4242:\t search cache=/etc/ld.so.cache
+      4242:\t  trying file=/lib/x86_64-linux-gnu/libc.so.6
+      4243:\t  trying file=/lib/x86_64-linux-gnu/libz.so.1
+      4242:\t
+      4242:\tfile=libc.so.6 [0];  generating link map
+      4243:\tfile=libz.so.1 [0];  generating link map
+      4242:\tfile=libselinux.so.1 [0];  needed by ./ls [0]
+      4242:\t  trying file=/lib/x86_64-linux-gnu/libselinux.so.1
+      4242:\tfile=libselinux.so.1 [0];  generating link map
+      4242:\tfile=libc.so.6 [0];  needed by /lib/x86_64-linux-gnu/libselinux.so.1 [0]
+      4242:\tfile=/usr/lib/plugin.so [0];  dynamically loaded by ./ls [0]
+      4242:\tfile=/usr/lib/plugin.so [0];  generating link map
+";
+
+    fn find<'a>(loads: &'a [Load], loader: &str, name: &str) -> &'a Load {
+        loads
+            .iter()
+            .find(|l| l.loader == loader && l.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn strips_namespace_from_object_names() {
+        assert_eq!(object_name("libc.so.6 [0]"), "libc.so.6");
+        assert_eq!(object_name(" /usr/bin/ls "), "/usr/bin/ls");
+    }
+
+    #[test]
+    fn parses_interleaved_processes() {
+        let loads = parse(LOG);
+        assert_eq!(loads.len(), 5);
+
+        let libc = find(&loads, "./ls", "libc.so.6");
+        assert_eq!(
+            libc.path.as_deref(),
+            Some("/lib/x86_64-linux-gnu/libc.so.6")
+        );
+        assert!(!libc.dlopen);
+
+        let libz = find(&loads, "/usr/bin/gzip", "libz.so.1");
+        assert_eq!(
+            libz.path.as_deref(),
+            Some("/lib/x86_64-linux-gnu/libz.so.1")
+        );
+    }
+
+    #[test]
+    fn reuses_paths_of_loaded_libraries() {
+        let loads = parse(LOG);
+        let libc = find(&loads, "/lib/x86_64-linux-gnu/libselinux.so.1", "libc.so.6");
+        assert_eq!(
+            libc.path.as_deref(),
+            Some("/lib/x86_64-linux-gnu/libc.so.6")
+        );
+    }
+
+    #[test]
+    fn parses_dlopen_loads() {
+        let loads = parse(LOG);
+        let plugin = find(&loads, "./ls", "/usr/lib/plugin.so");
+        assert_eq!(plugin.path.as_deref(), Some("/usr/lib/plugin.so"));
+        assert!(plugin.dlopen);
+    }
+}
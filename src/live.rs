@@ -0,0 +1,318 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{
+    errors::BingraphError,
+    graph::BinGraph,
+    node::{BinNode, Dependency, EdgeType, NodeType},
+};
+
+const DELETED_SUFFIX: &str = " (deleted)";
+
+/// A file mapped executable into the address space of a process.
+#[derive(Debug, Serialize, Clone)]
+pub struct MappedFile {
+    pub path: String,
+
+    /// The device the file was mapped from, encoded as `st_dev` is.
+    pub dev: u64,
+    pub inode: u64,
+
+    /// The file was unlinked, or replaced, after it was mapped.
+    pub deleted: bool,
+}
+
+/// A Process is a running process along with the files it has mapped.
+#[derive(Debug, Clone)]
+pub struct Process {
+    pub pid: u32,
+    pub exe: String,
+    pub exe_deleted: bool,
+    pub mapped: Vec<MappedFile>,
+}
+
+impl Process {
+    pub fn name(&self) -> String {
+        let file_name = self.exe.rsplit('/').next().unwrap_or(&self.exe);
+        format!("{}[{}]", file_name, self.pid)
+    }
+
+    /// Every path this process is currently using, including its executable.
+    pub fn paths(&self) -> HashSet<String> {
+        self.mapped
+            .iter()
+            .map(|m| m.path.clone())
+            .chain([self.exe.clone()])
+            .collect()
+    }
+}
+
+/// ProcessInfo is the runtime state recorded on the node of a process.
+#[derive(Debug, Serialize, Clone)]
+pub struct ProcessInfo {
+    pid: u32,
+    exe: String,
+    exe_deleted: bool,
+
+    /// Mapped libraries whose file has since been deleted or upgraded.
+    deleted_libraries: Vec<String>,
+}
+
+fn strip_deleted(path: &str) -> (String, bool) {
+    match path.strip_suffix(DELETED_SUFFIX) {
+        Some(p) => (p.to_string(), true),
+        None => (path.to_string(), false),
+    }
+}
+
+/// Parse a `major:minor` device from the maps, both in hex, into the
+/// encoding the kernel uses for `st_dev`.
+fn parse_device(s: &str) -> u64 {
+    let Some((major, minor)) = s.split_once(':') else {
+        return 0;
+    };
+    let major = u64::from_str_radix(major, 16).unwrap_or_default();
+    let minor = u64::from_str_radix(minor, 16).unwrap_or_default();
+
+    ((major & 0xfffff000) << 32)
+        | ((major & 0xfff) << 8)
+        | ((minor & 0xffffff00) << 12)
+        | (minor & 0xff)
+}
+
+/// Parse the file-backed executable mappings out of `/proc/<pid>/maps`,
+/// leaving out the executable itself.
+fn parse_maps(contents: &str, exe: &str) -> Vec<MappedFile> {
+    let mut seen: BTreeSet<String> = BTreeSet::new();
+    let mut mapped: Vec<MappedFile> = vec![];
+
+    for line in contents.lines() {
+        let fields: Vec<&str> = line.splitn(6, char::is_whitespace).collect();
+        let [_, perms, _, dev, inode, path] = fields[..] else {
+            continue;
+        };
+        let path = path.trim();
+        if !perms.contains('x') || !path.starts_with('/') {
+            continue;
+        }
+
+        let (path, deleted) = strip_deleted(path);
+        if path == exe || !seen.insert(path.clone()) {
+            continue;
+        }
+
+        mapped.push(MappedFile {
+            path,
+            dev: parse_device(dev),
+            inode: inode.parse().unwrap_or_default(),
+            deleted,
+        });
+    }
+
+    mapped
+}
+
+/// Read every process visible under the proc directory. Processes whose
+/// executable or maps cannot be read, such as kernel threads or those of
+/// other users when not running as root, are skipped.
+pub fn processes(proc_dir: &str) -> Vec<Process> {
+    let mut procs: Vec<Process> = vec![];
+
+    let Ok(entries) = fs::read_dir(proc_dir) else {
+        println!("unable to read {}", proc_dir);
+        return procs;
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let Ok(pid) = entry.file_name().to_string_lossy().parse::<u32>() else {
+            continue;
+        };
+
+        let Ok(exe) = fs::read_link(entry.path().join("exe")) else {
+            continue;
+        };
+        let Ok(maps) = fs::read_to_string(entry.path().join("maps")) else {
+            continue;
+        };
+
+        let (exe, exe_deleted) = strip_deleted(&exe.to_string_lossy());
+        let mapped = parse_maps(&maps, &exe);
+        procs.push(Process {
+            pid,
+            exe,
+            exe_deleted,
+            mapped,
+        });
+    }
+
+    procs.sort_by_key(|p| p.pid);
+    println!("read the mappings of {} processes", procs.len());
+    procs
+}
+
+/// Create the node of a running process, depending on its executable and
+/// every file it has mapped.
+fn process_node(p: &Process) -> BinNode {
+    let mut deps = vec![Dependency::new(&p.exe, EdgeType::Exec).with_paths(vec![p.exe.clone()])];
+    deps.extend(
+        p.mapped
+            .iter()
+            .map(|m| Dependency::new(&m.path, EdgeType::Mapped).with_paths(vec![m.path.clone()])),
+    );
+
+    let mut node = BinNode::new(p.name(), p.exe.clone(), NodeType::Process);
+    node.set_dependencies(deps);
+    node.set_process_info(ProcessInfo {
+        pid: p.pid,
+        exe: p.exe.clone(),
+        exe_deleted: p.exe_deleted,
+        deleted_libraries: p
+            .mapped
+            .iter()
+            .filter(|m| m.deleted)
+            .map(|m| m.path.clone())
+            .collect(),
+    });
+    node
+}
+
+/// Canonicalize a path, keeping it as is if it no longer exists.
+pub fn canonical(path: &str) -> String {
+    fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// Build the runtime graph of every visible process and the files it maps.
+/// When merging, the static graph of the binary and library paths is built
+/// alongside it, and every node and edge is marked as observed or not.
+pub fn live_graph(proc_dir: &str, merge: Option<String>) -> Result<BinGraph, BingraphError> {
+    let procs = processes(proc_dir);
+
+    let mut nodes = match merge {
+        Some(ref path) => BinGraph::scan(path),
+        None => vec![],
+    };
+
+    // Mapped files outside of the scanned paths, typically dlopen'ed
+    // plugins, still get a node of their own.
+    let mut known: HashSet<String> = nodes.iter().map(|n| canonical(n.absolute_path())).collect();
+    for p in procs.iter() {
+        for path in p.paths() {
+            if !known.insert(path.clone()) || !Path::new(&path).exists() {
+                continue;
+            }
+            match BinNode::from_path(Path::new(&path)) {
+                Ok(n) => nodes.extend(n),
+                Err(e) => println!("unable to create node at {:?}: {}", path, e),
+            }
+        }
+    }
+
+    for p in procs.iter() {
+        if p.exe_deleted {
+            println!("{} is running deleted executable {}", p.name(), p.exe);
+        }
+        let deleted: Vec<&str> = p
+            .mapped
+            .iter()
+            .filter(|m| m.deleted)
+            .map(|m| m.path.as_str())
+            .collect();
+        if !deleted.is_empty() {
+            println!("{} maps deleted libraries: {:?}", p.name(), deleted);
+        }
+
+        nodes.push(process_node(p));
+    }
+
+    let mut g = BinGraph::from_nodes(nodes)?;
+    if merge.is_some() {
+        // An edge is in use when a single process uses both of its ends.
+        let runs: Vec<HashSet<String>> = procs.iter().map(Process::paths).collect();
+        g.mark_observed(
+            |p| runs.iter().any(|r| r.contains(p)),
+            |s, t| runs.iter().any(|r| r.contains(s) && r.contains(t)),
+        );
+    }
+    Ok(g)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPS: &str = "\
+55d0c6a00000-55d0c6a04000 r--p 00000000 fd:01 1048602                    /usr/bin/cat
+55d0c6a04000-55d0c6a09000 r-xp 00004000 fd:01 1048602                    /usr/bin/cat
+7f1c2a000000-7f1c2a028000 r--p 00000000 fd:01 1055123                    /usr/lib/x86_64-linux-gnu/libc.so.6
+7f1c2a028000-7f1c2a1bd000 r-xp 00028000 fd:01 1055123                    /usr/lib/x86_64-linux-gnu/libc.so.6
+7f1c2a300000-7f1c2a301000 r-xp 00000000 fd:01 1055200                    /usr/lib/libold.so (deleted)
+7f1c2a400000-7f1c2a421000 rw-p 00000000 00:00 0                          [heap]
+7f1c2a500000-7f1c2a501000 r-xp 00000000 00:00 0                          [vdso]
+7f1c2a600000-7f1c2a601000 r-xp 00000000 fd:01 1055300                    /usr/lib/lib with space.so
+7f1c2a700000-7f1c2a702000 r-xp 00000000 fd:01 1055123                    /usr/lib/x86_64-linux-gnu/libc.so.6
+";
+
+    #[test]
+    fn strips_deleted_suffix() {
+        assert_eq!(
+            strip_deleted("/usr/lib/libold.so (deleted)"),
+            ("/usr/lib/libold.so".to_string(), true)
+        );
+        assert_eq!(
+            strip_deleted("/usr/lib/libc.so.6"),
+            ("/usr/lib/libc.so.6".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn encodes_devices_as_st_dev() {
+        assert_eq!(parse_device("08:02"), 0x802);
+        assert_eq!(parse_device("103:05"), 0x10305);
+        assert_eq!(parse_device("00:1a5"), 0x1000a5);
+        assert_eq!(parse_device("bogus"), 0);
+    }
+
+    #[test]
+    fn parses_executable_file_mappings() {
+        let mapped = parse_maps(MAPS, "/usr/bin/cat");
+        let paths: Vec<&str> = mapped.iter().map(|m| m.path.as_str()).collect();
+
+        assert_eq!(
+            paths,
+            [
+                "/usr/lib/x86_64-linux-gnu/libc.so.6",
+                "/usr/lib/libold.so",
+                "/usr/lib/lib with space.so",
+            ]
+        );
+        assert_eq!(mapped[0].dev, 0xfd01);
+        assert_eq!(mapped[0].inode, 1055123);
+        assert!(!mapped[0].deleted);
+        assert!(mapped[1].deleted);
+    }
+}
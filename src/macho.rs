@@ -0,0 +1,231 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::path::Path;
+
+use goblin::mach::{
+    constants::cputype::get_arch_name_from_types,
+    header::{MH_BUNDLE, MH_DYLIB, MH_EXECUTE},
+    load_command::CommandVariant,
+    MachO, MultiArch,
+};
+
+use crate::{
+    errors::BingraphError,
+    node::{BinNode, Dependency, EdgeType, NodeType},
+    pathiter::normalize,
+};
+
+/// Read a NUL terminated string out of a load command.
+fn c_str_at(data: &[u8], offset: usize) -> Option<&str> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[..end]).ok()
+}
+
+/// Expand the `@loader_path` and `@executable_path` prefixes of a path.
+///
+/// Without knowing which executable ends up loading a dylib, the directory of
+/// the node itself is used as `@executable_path`, which is exact for
+/// executables and the common case for dylibs bundled next to them.
+fn expand_prefix(path: &str, loader_path: &str) -> Option<String> {
+    let rest = path
+        .strip_prefix("@loader_path")
+        .or_else(|| path.strip_prefix("@executable_path"))?;
+    Some(normalize(
+        &Path::new(loader_path).join(rest.trim_start_matches('/')),
+    ))
+}
+
+/// Compute the absolute paths an install name may resolve to, in the order
+/// dyld would try them.
+fn candidate_paths(install_name: &str, rpaths: &[String], loader_path: &str) -> Vec<String> {
+    if let Some(rest) = install_name.strip_prefix("@rpath/") {
+        rpaths
+            .iter()
+            .map(|r| normalize(&Path::new(r).join(rest)))
+            .collect()
+    } else if let Some(p) = expand_prefix(install_name, loader_path) {
+        vec![p]
+    } else {
+        vec![normalize(Path::new(install_name))]
+    }
+}
+
+fn arch_name(macho: &MachO) -> String {
+    get_arch_name_from_types(macho.header.cputype(), macho.header.cpusubtype())
+        .map(String::from)
+        .unwrap_or_else(|| format!("cpu{}", macho.header.cputype()))
+}
+
+/// Create a node from a single architecture Mach-O image.
+pub fn node(
+    name: String,
+    absolute_path: String,
+    data: &[u8],
+    macho: &MachO,
+) -> Result<BinNode, BingraphError> {
+    let t = match macho.header.filetype {
+        MH_EXECUTE => NodeType::MachOExecutable,
+        MH_DYLIB => NodeType::MachODylib,
+        MH_BUNDLE => NodeType::MachOBundle,
+        _ => return Err(format!("{:?} is of unknown Mach-O file type", absolute_path).into()),
+    };
+
+    let loader_path = Path::new(&absolute_path)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let rpaths: Vec<String> = macho
+        .rpaths
+        .iter()
+        .map(|r| expand_prefix(r, &loader_path).unwrap_or(r.to_string()))
+        .collect();
+
+    let mut deps: Vec<Dependency> = vec![];
+    for cmd in macho.load_commands.iter() {
+        let (kind, dylib) = match &cmd.command {
+            CommandVariant::LoadDylib(c)
+            | CommandVariant::LazyLoadDylib(c)
+            | CommandVariant::LoadUpwardDylib(c) => (EdgeType::Needed, c),
+            CommandVariant::LoadWeakDylib(c) => (EdgeType::Weak, c),
+            CommandVariant::ReexportDylib(c) => (EdgeType::Reexport, c),
+            _ => continue,
+        };
+
+        if let Some(install_name) = c_str_at(data, cmd.offset + dylib.dylib.name as usize) {
+            let paths = candidate_paths(install_name, &rpaths, &loader_path);
+            deps.push(Dependency::new(install_name, kind).with_paths(paths));
+        }
+    }
+
+    let mut node = BinNode::new(name, absolute_path, t);
+    node.set_dependencies(deps);
+    node.set_arch(arch_name(macho));
+    Ok(node)
+}
+
+/// Split a universal binary into one node per architecture. Each node is
+/// named after the file and its architecture so they remain distinct.
+pub fn fat_nodes(
+    name: String,
+    absolute_path: String,
+    data: &[u8],
+    multi: &MultiArch,
+) -> Result<Vec<BinNode>, BingraphError> {
+    let mut nodes: Vec<BinNode> = vec![];
+    for arch in multi.arches()? {
+        let slice = arch.slice(data);
+        let macho = MachO::parse(slice, 0)?;
+        let arch_node_name = format!("{}[{}]", name, arch_name(&macho));
+        nodes.push(node(arch_node_name, absolute_path.clone(), slice, &macho)?);
+    }
+
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use goblin::mach::Mach;
+
+    use super::*;
+
+    const THIN: &[u8] = include_bytes!("../tests/fixtures/macho/thin.dylib");
+    const FAT: &[u8] = include_bytes!("../tests/fixtures/macho/fat");
+
+    #[test]
+    fn rpath_candidates_follow_rpath_order() {
+        let rpaths = vec!["/a/Frameworks".to_string(), "/opt/lib".to_string()];
+        assert_eq!(
+            candidate_paths("@rpath/libz.dylib", &rpaths, "/a/bin"),
+            vec!["/a/Frameworks/libz.dylib", "/opt/lib/libz.dylib"]
+        );
+        assert!(candidate_paths("@rpath/libz.dylib", &[], "/a/bin").is_empty());
+    }
+
+    #[test]
+    fn loader_and_executable_paths_are_relative_to_the_image() {
+        assert_eq!(
+            candidate_paths("@loader_path/../lib/libz.dylib", &[], "/a/bin"),
+            vec!["/a/lib/libz.dylib"]
+        );
+        assert_eq!(
+            candidate_paths("@executable_path/libz.dylib", &[], "/a/bin"),
+            vec!["/a/bin/libz.dylib"]
+        );
+        assert_eq!(
+            candidate_paths("/usr/lib/./libz.dylib", &[], "/a/bin"),
+            vec!["/usr/lib/libz.dylib"]
+        );
+    }
+
+    #[test]
+    fn thin_dylib() {
+        let Mach::Binary(macho) = Mach::parse(THIN).unwrap() else {
+            panic!("fixture is not a thin image");
+        };
+        let node = node(
+            "libthin.dylib".to_string(),
+            "/App/Contents/MacOS/libthin.dylib".to_string(),
+            THIN,
+            &macho,
+        )
+        .unwrap();
+
+        assert!(matches!(node.node_type(), NodeType::MachODylib));
+        assert_eq!(node.arch(), Some("x86_64"));
+
+        let deps = node.get_dependencies();
+        assert_eq!(deps.len(), 3);
+
+        assert_eq!(deps[0].name, "@rpath/libdep.dylib");
+        assert_eq!(deps[0].kind, EdgeType::Needed);
+        assert_eq!(
+            deps[0].paths,
+            vec![
+                "/App/Contents/Frameworks/libdep.dylib",
+                "/opt/lib/libdep.dylib"
+            ]
+        );
+
+        assert_eq!(deps[1].kind, EdgeType::Weak);
+        assert_eq!(deps[1].paths, vec!["/App/Contents/MacOS/libweak.dylib"]);
+
+        assert_eq!(deps[2].kind, EdgeType::Reexport);
+        assert_eq!(deps[2].paths, vec!["/usr/lib/libSystem.B.dylib"]);
+    }
+
+    #[test]
+    fn fat_binary_has_a_node_per_slice() {
+        let Mach::Fat(multi) = Mach::parse(FAT).unwrap() else {
+            panic!("fixture is not a fat binary");
+        };
+        let nodes = fat_nodes("app".to_string(), "/App/bin/app".to_string(), FAT, &multi).unwrap();
+
+        let names: Vec<String> = nodes.iter().map(|n| n.name()).collect();
+        assert_eq!(names, vec!["app[x86_64]", "app[arm64]"]);
+
+        for n in nodes.iter() {
+            assert!(matches!(n.node_type(), NodeType::MachOExecutable));
+            assert_eq!(n.get_dependencies()[0].paths, vec!["/App/lib/libfoo.dylib"]);
+        }
+        assert_eq!(nodes[0].get_dependencies().len(), 1);
+        assert_eq!(nodes[1].get_dependencies().len(), 2);
+    }
+}
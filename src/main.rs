@@ -19,39 +19,184 @@
 use std::{fs, io::Write};
 
 use clap::Parser;
-use cli::BingraphArgs;
+use cli::{BingraphArgs, BingraphCommand};
 use errors::BingraphError;
 use graph::BinGraph;
+use impact::ImpactReport;
+use initorder::InitOrderReport;
+use pkggraph::PackageGraph;
+use scc::Condensation;
+use why::{PathBounds, WhyReport};
 
+mod archive;
+mod betweenness;
 mod cli;
+mod closure;
+mod community;
+mod dominators;
 mod errors;
 mod graph;
+mod impact;
+mod initorder;
+mod jar;
+mod kmod;
+mod lddebug;
+mod live;
+mod macho;
+mod metrics;
 mod node;
+mod package;
 mod pathiter;
+mod pe;
+mod pkggraph;
+mod python;
+mod resolver;
+mod restart;
+mod scc;
+mod systemd;
+mod tree;
+mod wasm;
+mod why;
+mod winreg;
 
 fn main() -> Result<(), BingraphError> {
     let args = BingraphArgs::parse();
-    let g = match BinGraph::new(args.bin_path, args.lib_path) {
-        Ok(g) => g,
-        Err(e) => return Err(e),
+    let loader = pe::LoaderTables::load(&args.root);
+
+    let mut g = match &args.command {
+        Some(BingraphCommand::Kmod(kargs)) => {
+            kmod::module_graph(&kargs.modules_dir, kargs.release.clone(), kargs.check)?
+        }
+        Some(BingraphCommand::Python(pargs)) => {
+            python::python_graph(pargs.site_packages.clone(), &args.lib_path)?
+        }
+        Some(BingraphCommand::Live(largs)) => {
+            let merge = largs
+                .merge
+                .then(|| format!("{}:{}", args.bin_path, args.lib_path));
+            live::live_graph(&largs.proc_dir, merge)?
+        }
+        Some(BingraphCommand::LdDebug(dargs)) => {
+            let path = format!("{}:{}", args.bin_path, args.lib_path);
+            lddebug::trace_graph(&dargs.logs, &path)?
+        }
+        // Reports are written as they are, without building a graph.
+        Some(BingraphCommand::Restart(rargs)) => {
+            let report = restart::report(&rargs.proc_dir);
+            return write_file(&args.output, &serde_json::to_string_pretty(&report)?);
+        }
+        Some(BingraphCommand::Tree(targs)) => {
+            let trees = tree::dependency_trees(&targs.path, &args.lib_path, &loader)?;
+            return write_file(&args.output, &serde_json::to_string_pretty(&trees)?);
+        }
+        Some(BingraphCommand::Packages)
+        | Some(BingraphCommand::Impact(_))
+        | Some(BingraphCommand::Why(_))
+        | Some(BingraphCommand::InitOrder(_))
+        | None => {
+            let services = if args.services {
+                systemd::scan(&args.root)
+            } else {
+                vec![]
+            };
+            BinGraph::new(
+                args.bin_path.clone(),
+                args.lib_path.clone(),
+                services,
+                &loader,
+            )?
+        }
     };
 
-    let data = match serde_json::to_string_pretty(&g) {
-        Ok(d) => d,
-        Err(e) => return Err(e.into()),
+    // Reports over the graph do not include the metrics of its nodes, and
+    // only the ones grouping by package need the package databases.
+    let report = matches!(
+        args.command,
+        Some(BingraphCommand::Packages)
+            | Some(BingraphCommand::Impact(_))
+            | Some(BingraphCommand::Why(_))
+            | Some(BingraphCommand::InitOrder(_))
+    );
+    if !report {
+        g.compute_metrics(&args.metrics);
+    }
+
+    let db = match args.command {
+        Some(BingraphCommand::Why(_)) | Some(BingraphCommand::InitOrder(_)) => {
+            package::PackageDb::default()
+        }
+        Some(BingraphCommand::Packages) => package::PackageDb::load(&args.root)?,
+        // Elsewhere packages only enrich the graph, so do without them.
+        _ => package::PackageDb::load(&args.root).unwrap_or_else(|e| {
+            println!(
+                "unable to read package databases under {}: {}",
+                args.root, e
+            );
+            package::PackageDb::default()
+        }),
     };
+    if !db.is_empty() {
+        let unowned = g.attach_packages(&db);
+        println!("{} nodes are not owned by any package", unowned);
+    }
 
-    match fs::File::create(args.output) {
-        Ok(mut f) => f.write(data.as_bytes()).unwrap(),
-        Err(e) => return Err(e.into()),
+    let (data, graphviz) = match &args.command {
+        Some(BingraphCommand::Packages) => {
+            if db.is_empty() {
+                return Err(format!("no package databases found under {}", args.root).into());
+            }
+            let pg = PackageGraph::new(&g, &db, &args.metrics);
+            (serde_json::to_string_pretty(&pg)?, pg.serialize_graphviz())
+        }
+        Some(BingraphCommand::Impact(iargs)) => {
+            let report = ImpactReport::new(&g, &iargs.libraries)?;
+            let affected = report.affected_nodes();
+            (
+                serde_json::to_string_pretty(&report)?,
+                g.serialize_graphviz_of(|i| affected.contains(&i)),
+            )
+        }
+        Some(BingraphCommand::Why(wargs)) => {
+            let bounds = wargs.all.then_some(PathBounds {
+                max_depth: wargs.max_depth,
+                max_paths: wargs.max_paths,
+            });
+            let report = WhyReport::new(&g, &wargs.from, &wargs.to, bounds)?;
+            let nodes = report.path_nodes();
+            (
+                serde_json::to_string_pretty(&report)?,
+                g.serialize_graphviz_of(|i| nodes.contains(&i)),
+            )
+        }
+        Some(BingraphCommand::InitOrder(oargs)) => {
+            let report = InitOrderReport::new(&g, &oargs.executables)?;
+            let loaded = report.loaded_nodes();
+            (
+                serde_json::to_string_pretty(&report)?,
+                g.serialize_graphviz_layers_of(|i| loaded.contains(&i)),
+            )
+        }
+        _ => (serde_json::to_string_pretty(&g)?, g.serialize_graphviz()),
     };
 
-    if args.output_graphviz != "" {
-        match fs::File::create(args.output_graphviz) {
-            Ok(mut f) => f.write(g.serialize_graphviz().as_bytes()).unwrap(),
-            Err(e) => return Err(e.into()),
-        };
+    write_file(&args.output, &data)?;
+
+    if !args.output_graphviz.is_empty() {
+        write_file(&args.output_graphviz, &graphviz)?;
     }
 
+    if !args.output_condensation.is_empty() {
+        let condensation = Condensation::new(&g);
+        write_file(
+            &args.output_condensation,
+            &serde_json::to_string_pretty(&condensation)?,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_file(path: &str, data: &str) -> Result<(), BingraphError> {
+    fs::File::create(path)?.write_all(data.as_bytes())?;
     Ok(())
 }
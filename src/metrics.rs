@@ -0,0 +1,667 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{cmp::Reverse, collections::BinaryHeap, str::FromStr};
+
+use serde::Serialize;
+
+/// Metrics that can be computed for every node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Betweenness,
+    Katz,
+    Eigenvector,
+    Closeness,
+
+    /// PageRank over the dependency edges and over the reversed edges.
+    Pagerank,
+
+    /// HITS hub and authority scores.
+    Hits,
+
+    /// Core numbers of the undirected graph, and by in- and out-degree.
+    Kcore,
+
+    /// Louvain communities of the undirected graph.
+    Community,
+
+    /// Transitive dependency counts, sizes and depth, and the executables
+    /// depending on each library.
+    Closure,
+}
+
+impl Metric {
+    const ALL: [Metric; 9] = [
+        Metric::Betweenness,
+        Metric::Katz,
+        Metric::Eigenvector,
+        Metric::Closeness,
+        Metric::Pagerank,
+        Metric::Hits,
+        Metric::Kcore,
+        Metric::Community,
+        Metric::Closure,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Betweenness => "betweenness",
+            Metric::Katz => "katz",
+            Metric::Eigenvector => "eigenvector",
+            Metric::Closeness => "closeness",
+            Metric::Pagerank => "pagerank",
+            Metric::Hits => "hits",
+            Metric::Kcore => "kcore",
+            Metric::Community => "community",
+            Metric::Closure => "closure",
+        }
+    }
+
+    /// The parameters this metric accepts.
+    fn keys(&self) -> &'static [&'static str] {
+        match self {
+            Metric::Betweenness => &[
+                "normalized",
+                "endpoints",
+                "threads",
+                "samples",
+                "seed",
+                "budget",
+            ],
+            Metric::Katz => &["alpha", "beta", "tolerance", "max-iter"],
+            Metric::Eigenvector => &["tolerance", "max-iter"],
+            Metric::Closeness => &["normalized", "threads"],
+            Metric::Pagerank => &["alpha", "tolerance", "max-iter"],
+            Metric::Hits => &["tolerance", "max-iter"],
+            Metric::Kcore | Metric::Closure => &[],
+            Metric::Community => &["resolution", "seed"],
+        }
+    }
+
+    /// The parameters used when none are given.
+    fn defaults(&self) -> MetricParams {
+        let mut params = MetricParams::default();
+        match self {
+            Metric::Betweenness => {
+                params.normalized = Some(true);
+                params.endpoints = Some(true);
+            }
+            Metric::Katz => {
+                params.alpha = Some(0.1);
+                params.beta = Some(1.);
+                params.tolerance = Some(1e-6);
+                params.max_iter = Some(100);
+            }
+            Metric::Eigenvector | Metric::Hits => {
+                params.tolerance = Some(1e-6);
+                params.max_iter = Some(100);
+            }
+            Metric::Closeness => params.normalized = Some(true),
+            Metric::Pagerank => {
+                params.alpha = Some(0.85);
+                params.tolerance = Some(1e-6);
+                params.max_iter = Some(100);
+            }
+            Metric::Kcore | Metric::Closure => {}
+            Metric::Community => {
+                params.resolution = Some(1.);
+                params.seed = Some(0);
+            }
+        }
+        params
+    }
+}
+
+impl Serialize for Metric {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// Parameters of a metric. Only the ones the metric accepts are set.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetricParams {
+    /// Katz attenuation factor, or the PageRank damping factor.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beta: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tolerance: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_iter: Option<usize>,
+
+    /// For closeness, whether to use the Wasserman and Faust improvement for
+    /// graphs that are not connected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoints: Option<bool>,
+
+    /// Size of the thread pool to compute with, the global pool if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threads: Option<usize>,
+
+    /// Number of source nodes to sample, to approximate betweenness instead
+    /// of computing it exactly. With a budget, only used when falling back.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub samples: Option<usize>,
+
+    /// Seed for picking the sampled source nodes, or the order communities
+    /// are formed in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
+    /// Louvain resolution, where higher values find smaller communities.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<f64>,
+
+    /// Seconds the exact computation may be expected to take before falling
+    /// back to sampling.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget: Option<f64>,
+}
+
+/// A MetricSpec selects a metric along with its parameters, written on the
+/// command line as `name[:key=value...]`, such as `katz:alpha=0.2:max-iter=500`.
+#[derive(Debug, Clone)]
+pub struct MetricSpec {
+    pub metric: Metric,
+    pub params: MetricParams,
+}
+
+fn parse_param<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", key, value))
+}
+
+impl FromStr for MetricSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let name = parts.next().unwrap_or_default();
+        let metric = Metric::ALL
+            .into_iter()
+            .find(|m| m.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Metric::ALL.iter().map(Metric::name).collect();
+                format!(
+                    "unknown metric {}, expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })?;
+
+        let mut params = metric.defaults();
+        for param in parts {
+            let (key, value) = param
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, found {}", param))?;
+            if !metric.keys().contains(&key) {
+                return Err(format!("{} does not accept {}", name, key));
+            }
+
+            match key {
+                "alpha" => params.alpha = Some(parse_param(key, value)?),
+                "beta" => params.beta = Some(parse_param(key, value)?),
+                "tolerance" => params.tolerance = Some(parse_param(key, value)?),
+                "max-iter" => params.max_iter = Some(parse_param(key, value)?),
+                "normalized" => params.normalized = Some(parse_param(key, value)?),
+                "endpoints" => params.endpoints = Some(parse_param(key, value)?),
+                "threads" => params.threads = Some(parse_param(key, value)?),
+                "samples" => params.samples = Some(parse_param(key, value)?),
+                "seed" => params.seed = Some(parse_param(key, value)?),
+                "budget" => params.budget = Some(parse_param(key, value)?),
+                "resolution" => params.resolution = Some(parse_param(key, value)?),
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(Self { metric, params })
+    }
+}
+
+/// MetricStatus describes how the computation of a metric ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricStatus {
+    /// A direct computation finished.
+    Computed,
+
+    /// An iterative computation reached its tolerance.
+    Converged,
+
+    /// An iterative computation ran out of iterations, so no values are set.
+    NotConverged,
+
+    /// The computation returned an error, so no values are set.
+    Failed,
+}
+
+impl Serialize for MetricStatus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            MetricStatus::Computed => serializer.serialize_str("computed"),
+            MetricStatus::Converged => serializer.serialize_str("converged"),
+            MetricStatus::NotConverged => serializer.serialize_str("not_converged"),
+            MetricStatus::Failed => serializer.serialize_str("failed"),
+        }
+    }
+}
+
+/// Method describes whether a metric was computed exactly or estimated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Exact,
+
+    /// Estimated from a sample of source nodes.
+    Sampled,
+}
+
+impl Serialize for Method {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Method::Exact => serializer.serialize_str("exact"),
+            Method::Sampled => serializer.serialize_str("sampled"),
+        }
+    }
+}
+
+/// A MetricRun records how a metric was computed over the graph.
+#[derive(Debug, Serialize)]
+pub struct MetricRun {
+    metric: Metric,
+    parameters: MetricParams,
+    status: MetricStatus,
+
+    /// For metrics that may be estimated, how they were computed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<Method>,
+
+    /// Iterations taken, where the implementation reports them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iterations: Option<usize>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl MetricRun {
+    pub fn new(spec: &MetricSpec, status: MetricStatus) -> Self {
+        Self {
+            metric: spec.metric,
+            parameters: spec.params.clone(),
+            status,
+            method: None,
+            iterations: None,
+            error: None,
+        }
+    }
+
+    /// Record how a metric that may be estimated was computed, along with
+    /// the parameters that were actually used.
+    pub fn with_method(mut self, method: Method, parameters: MetricParams) -> Self {
+        self.method = Some(method);
+        self.parameters = parameters;
+        self
+    }
+
+    /// Record the outcome of an iterative computation.
+    pub fn iterative(spec: &MetricSpec, iterations: usize, converged: bool) -> Self {
+        let status = if converged {
+            MetricStatus::Converged
+        } else {
+            println!(
+                "{} did not converge after {} iterations",
+                spec.metric.name(),
+                iterations
+            );
+            MetricStatus::NotConverged
+        };
+        let mut run = Self::new(spec, status);
+        run.iterations = Some(iterations);
+        run
+    }
+
+    pub fn failed(spec: &MetricSpec, error: String) -> Self {
+        let mut run = Self::new(spec, MetricStatus::Failed);
+        run.error = Some(error);
+        run
+    }
+}
+
+/// Run `f` within a thread pool of the requested size, or the global pool.
+pub fn with_threads<T: Send>(threads: Option<usize>, f: impl FnOnce() -> T + Send) -> T {
+    match threads.and_then(|n| rayon::ThreadPoolBuilder::new().num_threads(n).build().ok()) {
+        Some(pool) => pool.install(f),
+        None => f(),
+    }
+}
+
+/// The outcome of an iterative computation: its values if it converged, and
+/// the iterations it took.
+pub type Iterated<T> = (Option<T>, usize);
+
+/// Adjacency holds the distinct successors and predecessors of every node,
+/// without self loops.
+pub struct Adjacency {
+    succ: Vec<Vec<usize>>,
+    pred: Vec<Vec<usize>>,
+}
+
+impl Adjacency {
+    pub fn new(n: usize, edges: impl Iterator<Item = (usize, usize)>) -> Self {
+        let mut succ: Vec<Vec<usize>> = vec![vec![]; n];
+        let mut pred: Vec<Vec<usize>> = vec![vec![]; n];
+        for (s, t) in edges {
+            if s != t && !succ[s].contains(&t) {
+                succ[s].push(t);
+                pred[t].push(s);
+            }
+        }
+        Self { succ, pred }
+    }
+
+    fn len(&self) -> usize {
+        self.succ.len()
+    }
+
+    /// Compute PageRank by power iteration, following edges from `out` to
+    /// the nodes listed in it. Rank held by nodes without any edges is spread
+    /// evenly over every node.
+    fn pagerank(out: &[Vec<usize>], params: &MetricParams) -> Iterated<Vec<f64>> {
+        let n = out.len();
+        if n == 0 {
+            return (Some(vec![]), 0);
+        }
+
+        let damping = params.alpha.unwrap_or(0.85);
+        let tolerance = params.tolerance.unwrap_or(1e-6);
+        let mut rank = vec![1. / n as f64; n];
+        for iteration in 1..=params.max_iter.unwrap_or(100) {
+            let dangling: f64 = (0..n).filter(|v| out[*v].is_empty()).map(|v| rank[v]).sum();
+            let base = (1. - damping) / n as f64 + damping * dangling / n as f64;
+
+            let mut next = vec![base; n];
+            for v in 0..n {
+                let share = damping * rank[v] / out[v].len().max(1) as f64;
+                for t in out[v].iter() {
+                    next[*t] += share;
+                }
+            }
+
+            let delta: f64 = next
+                .iter()
+                .zip(rank.iter())
+                .map(|(a, b)| (a - b).abs())
+                .sum();
+            rank = next;
+            if delta < tolerance * n as f64 {
+                return (Some(rank), iteration);
+            }
+        }
+
+        (None, params.max_iter.unwrap_or(100))
+    }
+
+    /// PageRank over the dependency edges, ranking nodes that much of the
+    /// graph depends upon.
+    pub fn pagerank_in(&self, params: &MetricParams) -> Iterated<Vec<f64>> {
+        Self::pagerank(&self.succ, params)
+    }
+
+    /// PageRank over the reversed edges, ranking nodes that depend upon much
+    /// of the graph.
+    pub fn pagerank_out(&self, params: &MetricParams) -> Iterated<Vec<f64>> {
+        Self::pagerank(&self.pred, params)
+    }
+
+    /// Compute HITS hub and authority scores, each normalized to sum to one.
+    pub fn hits(&self, params: &MetricParams) -> Iterated<(Vec<f64>, Vec<f64>)> {
+        let n = self.len();
+        let tolerance = params.tolerance.unwrap_or(1e-6);
+        let max_iter = params.max_iter.unwrap_or(100);
+        let normalize = |v: &mut Vec<f64>| {
+            let sum: f64 = v.iter().sum();
+            if sum > 0. {
+                v.iter_mut().for_each(|x| *x /= sum);
+            }
+        };
+
+        let mut hubs = vec![1. / n.max(1) as f64; n];
+        for iteration in 1..=max_iter {
+            let mut authorities: Vec<f64> = (0..n)
+                .map(|v| self.pred[v].iter().fold(0., |sum, p| sum + hubs[*p]))
+                .collect();
+            normalize(&mut authorities);
+
+            let mut next: Vec<f64> = (0..n)
+                .map(|v| self.succ[v].iter().fold(0., |sum, s| sum + authorities[*s]))
+                .collect();
+            normalize(&mut next);
+
+            let delta: f64 = next
+                .iter()
+                .zip(hubs.iter())
+                .map(|(a, b)| (a - b).abs())
+                .sum();
+            hubs = next;
+            if delta < tolerance * n as f64 {
+                return (Some((hubs, authorities)), iteration);
+            }
+        }
+
+        (None, max_iter)
+    }
+
+    /// Peel off nodes in order of their degree, where removing a node lowers
+    /// the degree of the nodes in `affected`. A node's core number is the
+    /// highest degree seen up to its removal.
+    fn cores(mut degree: Vec<usize>, affected: impl Fn(usize) -> Vec<usize>) -> Vec<u32> {
+        let n = degree.len();
+        let mut core = vec![0; n];
+        let mut removed = vec![false; n];
+        let mut heap: BinaryHeap<Reverse<(usize, usize)>> =
+            (0..n).map(|v| Reverse((degree[v], v))).collect();
+
+        let mut k = 0;
+        while let Some(Reverse((d, v))) = heap.pop() {
+            if removed[v] || d != degree[v] {
+                continue;
+            }
+            k = k.max(d);
+            core[v] = k as u32;
+            removed[v] = true;
+
+            for u in affected(v) {
+                if !removed[u] && degree[u] > 0 {
+                    degree[u] -= 1;
+                    heap.push(Reverse((degree[u], u)));
+                }
+            }
+        }
+
+        core
+    }
+
+    /// Core numbers of the undirected projection of the graph.
+    pub fn core_numbers(&self) -> Vec<u32> {
+        let neighbors: Vec<Vec<usize>> = (0..self.len())
+            .map(|v| {
+                let mut n = self.succ[v].clone();
+                n.extend(self.pred[v].iter().filter(|p| !self.succ[v].contains(p)));
+                n
+            })
+            .collect();
+        let degree = neighbors.iter().map(Vec::len).collect();
+        Self::cores(degree, |v| neighbors[v].clone())
+    }
+
+    /// Core numbers counting only the edges into each node.
+    pub fn in_core_numbers(&self) -> Vec<u32> {
+        let degree = self.pred.iter().map(Vec::len).collect();
+        Self::cores(degree, |v| self.succ[v].clone())
+    }
+
+    /// Core numbers counting only the edges out of each node.
+    pub fn out_core_numbers(&self) -> Vec<u32> {
+        let degree = self.succ.iter().map(Vec::len).collect();
+        Self::cores(degree, |v| self.pred[v].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adjacency(n: usize, edges: &[(usize, usize)]) -> Adjacency {
+        Adjacency::new(n, edges.iter().copied())
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-4, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn pagerank_of_cycle_is_uniform() {
+        let adj = adjacency(3, &[(0, 1), (1, 2), (2, 0)]);
+        let (ranks, _) = adj.pagerank_in(&Metric::Pagerank.defaults());
+        assert_close(&ranks.unwrap(), &[1. / 3.; 3]);
+    }
+
+    #[test]
+    fn pagerank_spreads_dangling_rank() {
+        // The sink passes its rank evenly to every node, so with damping d
+        // the source holds (1 - d) / 2 + d * r1 / 2 = 0.5 / 1.425.
+        let adj = adjacency(2, &[(0, 1)]);
+        let params = Metric::Pagerank.defaults();
+
+        let (ranks, _) = adj.pagerank_in(&params);
+        let ranks = ranks.unwrap();
+        assert_close(&ranks, &[0.5 / 1.425, 1. - 0.5 / 1.425]);
+        assert!((ranks.iter().sum::<f64>() - 1.).abs() < 1e-9);
+
+        let (reverse, _) = adj.pagerank_out(&params);
+        assert_close(&reverse.unwrap(), &[1. - 0.5 / 1.425, 0.5 / 1.425]);
+    }
+
+    #[test]
+    fn hits_of_star() {
+        let adj = adjacency(4, &[(0, 1), (0, 2), (0, 3)]);
+        let (scores, _) = adj.hits(&Metric::Hits.defaults());
+        let (hubs, authorities) = scores.unwrap();
+        assert_close(&hubs, &[1., 0., 0., 0.]);
+        assert_close(&authorities, &[0., 1. / 3., 1. / 3., 1. / 3.]);
+    }
+
+    #[test]
+    fn core_numbers_of_triangle_with_pendant() {
+        let adj = adjacency(4, &[(0, 1), (1, 2), (2, 0), (2, 3)]);
+        assert_eq!(adj.core_numbers(), [2, 2, 2, 1]);
+    }
+
+    #[test]
+    fn directed_core_numbers_differ() {
+        // A path into a cycle: the start of the path has no edges in, but
+        // every node has one out.
+        let adj = adjacency(4, &[(3, 0), (0, 1), (1, 2), (2, 0)]);
+        assert_eq!(adj.in_core_numbers(), [1, 1, 1, 0]);
+        assert_eq!(adj.out_core_numbers(), [1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn iterations_run_out_without_values() {
+        let adj = adjacency(2, &[(0, 1)]);
+        let mut params = Metric::Pagerank.defaults();
+        params.max_iter = Some(1);
+        assert_eq!(adj.pagerank_in(&params), (None, 1));
+
+        let adj = adjacency(3, &[(0, 1), (1, 2), (0, 2)]);
+        let mut params = Metric::Hits.defaults();
+        params.max_iter = Some(1);
+        assert_eq!(adj.hits(&params), (None, 1));
+    }
+
+    #[test]
+    fn parses_metric_specs() {
+        let spec: MetricSpec = "katz:alpha=0.2:max-iter=500".parse().unwrap();
+        assert_eq!(spec.metric, Metric::Katz);
+        assert_eq!(spec.params.alpha, Some(0.2));
+        assert_eq!(spec.params.max_iter, Some(500));
+        // Parameters not given keep their defaults.
+        assert_eq!(spec.params.beta, Some(1.));
+        assert_eq!(spec.params.tolerance, Some(1e-6));
+
+        let spec: MetricSpec = "betweenness:threads=4:samples=100:seed=7".parse().unwrap();
+        assert_eq!(spec.params.threads, Some(4));
+        assert_eq!(spec.params.samples, Some(100));
+        assert_eq!(spec.params.seed, Some(7));
+        assert_eq!(spec.params.normalized, Some(true));
+        assert_eq!(spec.params.budget, None);
+
+        let spec: MetricSpec = "kcore".parse().unwrap();
+        assert_eq!(spec.metric, Metric::Kcore);
+        assert_eq!(
+            serde_json::to_string(&spec.params).unwrap(),
+            "{}".to_string()
+        );
+    }
+
+    #[test]
+    fn rejects_bad_metric_specs() {
+        let err = |s: &str| s.parse::<MetricSpec>().unwrap_err();
+
+        assert!(err("bogus").starts_with("unknown metric bogus"));
+        assert!(err("").starts_with("unknown metric"));
+        assert_eq!(err("katz:alpha"), "expected key=value, found alpha");
+        assert_eq!(err("katz:samples=3"), "katz does not accept samples");
+        assert_eq!(err("kcore:seed=1"), "kcore does not accept seed");
+        assert_eq!(err("katz:alpha=high"), "invalid value for alpha: high");
+        assert_eq!(
+            err("betweenness:threads=-1"),
+            "invalid value for threads: -1"
+        );
+    }
+
+    #[test]
+    fn records_iterative_outcomes() {
+        let spec: MetricSpec = "pagerank".parse().unwrap();
+        let status = |run: &MetricRun| serde_json::to_value(run).unwrap()["status"].clone();
+
+        let run = MetricRun::iterative(&spec, 100, false);
+        assert_eq!(status(&run), "not_converged");
+        assert_eq!(run.iterations, Some(100));
+
+        let run = MetricRun::iterative(&spec, 12, true);
+        assert_eq!(status(&run), "converged");
+        assert_eq!(run.iterations, Some(12));
+    }
+}
@@ -16,12 +16,29 @@
 *	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
  */
 
-use std::fs::{self, DirEntry};
+use std::{
+    fs::{self, DirEntry},
+    path::Path,
+};
 
-use goblin::Object;
+use goblin::{
+    elf::{dynamic::DT_DEPAUDIT, header::ET_REL, Elf},
+    mach::Mach,
+    Object,
+};
 use serde::Serialize;
 
-use crate::errors::BingraphError;
+use crate::{
+    archive::{self, ExportedSymbol, ObjectMember},
+    errors::BingraphError,
+    jar::{self, JarInfo},
+    kmod,
+    live::ProcessInfo,
+    macho,
+    package::Package,
+    pathiter::normalize,
+    pe, python, wasm,
+};
 
 /// A BinNode is a wrapper around a filesystem node on the searched system. This
 /// includes shared libraries, ELF binaries, and interpreted executables.
@@ -32,8 +49,28 @@ pub struct BinNode {
 
     node_type: NodeType,
 
+    /// The architecture of this node, only set for formats where the loader
+    /// refuses dependencies built for another architecture.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arch: Option<String>,
+
+    #[serde(skip)]
+    dependencies: Vec<Dependency>,
+
+    /// Symbols exported by a shared library, used to link static objects.
+    /// ELF exports are only read when there are static objects to link or a
+    /// symbol analysis asks for them, see `load_exports` and `with_symbols`.
     #[serde(skip)]
-    dependencies: Vec<String>,
+    exports: Vec<ExportedSymbol>,
+
+    /// Symbols an ELF object leaves undefined for its dependencies to provide,
+    /// only read by `with_symbols`.
+    #[serde(skip)]
+    imports: Vec<String>,
+
+    /// The relocatable objects making up a static archive or object file.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    members: Vec<ObjectMember>,
 
     in_degree: u32,
     out_degree: u32,
@@ -42,22 +79,279 @@ pub struct BinNode {
     katz_centrality: Option<f64>,
     eigen_centrality: Option<f64>,
     closeness_centrality: Option<f64>,
+
+    /// PageRank over the dependency edges, and over the reversed edges.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pagerank: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reverse_pagerank: Option<f64>,
+
+    /// HITS scores: hubs depend on good authorities, which are depended
+    /// upon by good hubs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hub_score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    authority_score: Option<f64>,
+
+    /// The largest k such that this node is in the k-core of the undirected
+    /// graph, and of the graph counting only in- or out-degree. Every node of
+    /// an acyclic graph has directed core numbers of zero, so those are only
+    /// set above zero around cycles.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    core_number: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    in_core_number: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    out_core_number: Option<u32>,
+
+    /// On-disk size of the file holding this node, in bytes.
+    size: u64,
+
+    /// Number of nodes this node transitively depends upon.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transitive_dependencies: Option<u32>,
+
+    /// Total on-disk size of every node this node transitively depends upon.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transitive_size: Option<u64>,
+
+    /// Number of edges along the longest dependency chain from this node.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_depth: Option<u32>,
+
+    /// For libraries, the number of executables transitively depending on it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dependent_executables: Option<u32>,
+
+    /// For libraries, the number of executables with some dependency that can
+    /// only be reached through this library.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dominated_executables: Option<u32>,
+
+    /// Number of nodes that every executable can only reach through this one.
+    dominated_nodes: u32,
+
+    /// Layer of this node in the initialization order: nodes only depend on
+    /// nodes in lower layers at load time, so they are initialized after
+    /// them. Members of a cycle share a layer.
+    init_layer: u32,
+
+    /// The community this node was clustered into, when detected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    community: Option<u32>,
+
+    /// The strongly connected component holding this node. Components are
+    /// numbered in reverse topological order, so dependencies come first.
+    scc_id: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    module_info: Option<kmod::ModuleInfo>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jar_info: Option<JarInfo>,
+
+    /// The Python distribution that installed this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distribution: Option<String>,
+
+    /// The distribution package owning this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package: Option<Package>,
+
+    /// Set when package databases were read but none of them own this file.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    unowned: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    process_info: Option<ProcessInfo>,
+
+    /// Whether a running process was seen using this node, only set when
+    /// runtime information was collected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    observed: Option<bool>,
 }
 
 impl BinNode {
+    pub fn new(name: String, absolute_path: String, node_type: NodeType) -> Self {
+        Self {
+            name,
+            absolute_path,
+            node_type,
+            arch: None,
+            dependencies: vec![],
+            exports: vec![],
+            imports: vec![],
+            members: vec![],
+            in_degree: 0,
+            out_degree: 0,
+            betweenness_centrality: None,
+            katz_centrality: None,
+            eigen_centrality: None,
+            closeness_centrality: None,
+            pagerank: None,
+            reverse_pagerank: None,
+            hub_score: None,
+            authority_score: None,
+            core_number: None,
+            in_core_number: None,
+            out_core_number: None,
+            size: 0,
+            transitive_dependencies: None,
+            transitive_size: None,
+            max_depth: None,
+            dependent_executables: None,
+            dominated_executables: None,
+            dominated_nodes: 0,
+            community: None,
+            init_layer: 0,
+            scc_id: 0,
+            module_info: None,
+            jar_info: None,
+            distribution: None,
+            package: None,
+            unowned: false,
+            process_info: None,
+            observed: None,
+        }
+    }
+
     pub fn name(&self) -> String {
         self.name.clone()
     }
 
+    pub fn absolute_path(&self) -> &str {
+        &self.absolute_path
+    }
+
+    pub fn node_type(&self) -> &NodeType {
+        &self.node_type
+    }
+
+    pub fn arch(&self) -> Option<&str> {
+        self.arch.as_deref()
+    }
+
+    pub fn set_arch(&mut self, arch: String) {
+        self.arch = Some(arch);
+    }
+
+    pub fn set_dependencies(&mut self, deps: Vec<Dependency>) {
+        self.dependencies = deps;
+    }
+
+    pub fn add_dependencies(&mut self, deps: Vec<Dependency>) {
+        self.dependencies.extend(deps);
+    }
+
+    pub fn exports(&self) -> &Vec<ExportedSymbol> {
+        &self.exports
+    }
+
+    pub fn set_exports(&mut self, exports: Vec<ExportedSymbol>) {
+        self.exports = exports;
+    }
+
+    pub fn imports(&self) -> &Vec<String> {
+        &self.imports
+    }
+
+    /// Read the dynamic symbols an ELF node imports and exports from its file.
+    fn read_symbols(&self) -> Option<(Vec<String>, Vec<ExportedSymbol>)> {
+        if !matches!(
+            self.node_type,
+            NodeType::ELFBinary | NodeType::ELFLibrary | NodeType::PythonExtension
+        ) {
+            return None;
+        }
+
+        let file = fs::read(&self.absolute_path).ok()?;
+        let elf = Elf::parse(&file).ok()?;
+        let exports = if elf.is_lib {
+            archive::exported_symbols(&elf)
+        } else {
+            vec![]
+        };
+        Some((archive::imported_symbols(&elf), exports))
+    }
+
+    /// Load the exports of an ELF shared library, for linking static objects.
+    pub fn load_exports(&mut self) {
+        if let Some((_, exports)) = self.read_symbols() {
+            self.exports = exports;
+        }
+    }
+
+    /// Return a copy of this node with its ELF imports and exports loaded.
+    pub fn with_symbols(&self) -> Self {
+        let mut node = self.clone();
+        if let Some((imports, exports)) = self.read_symbols() {
+            node.imports = imports;
+            node.exports = exports;
+        }
+        node
+    }
+
+    pub fn jar_info(&self) -> Option<&JarInfo> {
+        self.jar_info.as_ref()
+    }
+
+    pub fn set_jar_info(&mut self, info: JarInfo) {
+        self.jar_info = Some(info);
+    }
+
+    pub fn distribution(&self) -> Option<&str> {
+        self.distribution.as_deref()
+    }
+
+    pub fn set_distribution(&mut self, distribution: String) {
+        self.distribution = Some(distribution);
+    }
+
+    pub fn set_process_info(&mut self, info: ProcessInfo) {
+        self.process_info = Some(info);
+    }
+
+    pub fn set_observed(&mut self, observed: bool) {
+        self.observed = Some(observed);
+    }
+
+    pub fn package(&self) -> Option<&Package> {
+        self.package.as_ref()
+    }
+
+    /// Record the owning package of this node, or flag it as unowned.
+    pub fn set_package(&mut self, package: Option<Package>) {
+        self.unowned = package.is_none();
+        self.package = package;
+    }
+
+    pub fn members(&self) -> &Vec<ObjectMember> {
+        &self.members
+    }
+
     pub fn format_graphviz(&self) -> String {
         let color: &str = match self.node_type {
             NodeType::ELFBinary => "blue",
             NodeType::ELFLibrary => "green",
             NodeType::PortableExecutable => "pink",
+            NodeType::PortableDll => "lightpink",
             NodeType::InterpretedExecutable => "red",
+            NodeType::KernelModule => "orange",
+            NodeType::MachOExecutable => "purple",
+            NodeType::MachODylib => "cyan",
+            NodeType::MachOBundle => "yellow",
+            NodeType::ELFObject => "gray",
+            NodeType::StaticArchive => "brown",
+            NodeType::WasmModule => "violet",
+            NodeType::WasmComponent => "orchid",
+            NodeType::JavaArchive => "tan",
+            NodeType::JavaModule => "gold",
+            NodeType::PythonExtension => "steelblue",
+            NodeType::Service => "coral",
+            NodeType::Process => "crimson",
         };
 
-        return format!(
+        format!(
             "  \"{}\" [style=filled, color=\"{}\", tooltip=\"Absolute Path: {}\\nOutdegree: {}\\nIndegree: {}\\nBetweeness: {}\\nKatz: {}\\nEigen: {}\\nCloseness: {}\"];\n",
             self.name(),
             color,
@@ -68,7 +362,63 @@ impl BinNode {
             self.katz_centrality.unwrap_or_default(),
             self.eigen_centrality.unwrap_or_default(),
             self.closeness_centrality.unwrap_or_default(),
-        );
+        )
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn set_transitive_dependencies(&mut self, n: u32) {
+        self.transitive_dependencies = Some(n);
+    }
+
+    pub fn set_transitive_size(&mut self, size: u64) {
+        self.transitive_size = Some(size);
+    }
+
+    pub fn set_max_depth(&mut self, depth: u32) {
+        self.max_depth = Some(depth);
+    }
+
+    pub fn set_dependent_executables(&mut self, n: u32) {
+        self.dependent_executables = Some(n);
+    }
+
+    pub fn set_dominated_executables(&mut self, n: u32) {
+        self.dominated_executables = Some(n);
+    }
+
+    pub fn set_dominated_nodes(&mut self, n: u32) {
+        self.dominated_nodes = n;
+    }
+
+    pub fn community(&self) -> Option<u32> {
+        self.community
+    }
+
+    pub fn set_community(&mut self, id: u32) {
+        self.community = Some(id);
+    }
+
+    pub fn init_layer(&self) -> u32 {
+        self.init_layer
+    }
+
+    pub fn set_init_layer(&mut self, layer: u32) {
+        self.init_layer = layer;
+    }
+
+    pub fn scc_id(&self) -> u32 {
+        self.scc_id
+    }
+
+    pub fn set_scc_id(&mut self, id: u32) {
+        self.scc_id = id;
+    }
+
+    pub fn betweenness_centrality(&self) -> Option<f64> {
+        self.betweenness_centrality
     }
 
     pub fn set_betweeness_centrality(&mut self, c: f64) {
@@ -87,6 +437,34 @@ impl BinNode {
         self.closeness_centrality = Some(c);
     }
 
+    pub fn set_pagerank(&mut self, rank: f64) {
+        self.pagerank = Some(rank);
+    }
+
+    pub fn set_reverse_pagerank(&mut self, rank: f64) {
+        self.reverse_pagerank = Some(rank);
+    }
+
+    pub fn set_hub_score(&mut self, score: f64) {
+        self.hub_score = Some(score);
+    }
+
+    pub fn set_authority_score(&mut self, score: f64) {
+        self.authority_score = Some(score);
+    }
+
+    pub fn set_core_number(&mut self, k: u32) {
+        self.core_number = Some(k);
+    }
+
+    pub fn set_in_core_number(&mut self, k: u32) {
+        self.in_core_number = Some(k);
+    }
+
+    pub fn set_out_core_number(&mut self, k: u32) {
+        self.out_core_number = Some(k);
+    }
+
     pub fn set_in_degree(&mut self, v: u32) {
         self.in_degree = v;
     }
@@ -95,7 +473,7 @@ impl BinNode {
         self.out_degree = v;
     }
 
-    pub fn get_dependencies(&self) -> &Vec<String> {
+    pub fn get_dependencies(&self) -> &Vec<Dependency> {
         &self.dependencies
     }
 
@@ -104,13 +482,59 @@ impl BinNode {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum NodeType {
     ELFBinary,
     ELFLibrary,
     PortableExecutable,
+    PortableDll,
     #[allow(unused)]
     InterpretedExecutable,
+    KernelModule,
+    MachOExecutable,
+    MachODylib,
+    MachOBundle,
+    ELFObject,
+    StaticArchive,
+    WasmModule,
+    WasmComponent,
+    JavaArchive,
+    JavaModule,
+    PythonExtension,
+    Service,
+    Process,
+}
+
+impl NodeType {
+    /// Returns whether nodes of this type are programs that get executed,
+    /// rather than loaded into another program.
+    pub fn is_executable(&self) -> bool {
+        matches!(
+            self,
+            NodeType::ELFBinary
+                | NodeType::InterpretedExecutable
+                | NodeType::MachOExecutable
+                | NodeType::PortableExecutable
+        )
+    }
+
+    /// Returns whether nodes of this type are WebAssembly modules or components,
+    /// whose imports are resolved by name rather than by file.
+    pub fn is_wasm(&self) -> bool {
+        matches!(self, NodeType::WasmModule | NodeType::WasmComponent)
+    }
+
+    /// Returns whether nodes of this type are PE images, loaded following
+    /// the Windows DLL search order.
+    pub fn is_portable(&self) -> bool {
+        matches!(self, NodeType::PortableExecutable | NodeType::PortableDll)
+    }
+
+    /// Returns whether nodes of this type are loaded by other nodes, rather
+    /// than being programs, services or processes themselves.
+    pub fn is_library(&self) -> bool {
+        !self.is_executable() && !matches!(self, NodeType::Service | NodeType::Process)
+    }
 }
 
 impl Serialize for NodeType {
@@ -122,68 +546,355 @@ impl Serialize for NodeType {
             NodeType::ELFBinary => serializer.serialize_str("elf_binary"),
             NodeType::ELFLibrary => serializer.serialize_str("elf_library"),
             NodeType::PortableExecutable => serializer.serialize_str("pe"),
+            NodeType::PortableDll => serializer.serialize_str("pe_dll"),
             NodeType::InterpretedExecutable => serializer.serialize_str("interp"),
+            NodeType::KernelModule => serializer.serialize_str("kernel_module"),
+            NodeType::MachOExecutable => serializer.serialize_str("macho_executable"),
+            NodeType::MachODylib => serializer.serialize_str("macho_dylib"),
+            NodeType::MachOBundle => serializer.serialize_str("macho_bundle"),
+            NodeType::ELFObject => serializer.serialize_str("elf_object"),
+            NodeType::StaticArchive => serializer.serialize_str("static_archive"),
+            NodeType::WasmModule => serializer.serialize_str("wasm_module"),
+            NodeType::WasmComponent => serializer.serialize_str("wasm_component"),
+            NodeType::JavaArchive => serializer.serialize_str("jar"),
+            NodeType::JavaModule => serializer.serialize_str("java_module"),
+            NodeType::PythonExtension => serializer.serialize_str("python_extension"),
+            NodeType::Service => serializer.serialize_str("service"),
+            NodeType::Process => serializer.serialize_str("process"),
         }
     }
 }
 
-impl TryFrom<DirEntry> for BinNode {
-    type Error = BingraphError;
+/// DT_FILTER and DT_AUXILIARY are Solaris/GNU extensions that goblin does not
+/// export constants for.
+const DT_AUXILIARY: u64 = 0x7fff_fffd;
+const DT_FILTER: u64 = 0x7fff_ffff;
+
+/// A Dependency is a single loader-level relationship declared by a node,
+/// along with the kind of relationship it is.
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub name: String,
+    pub kind: EdgeType,
+
+    /// Absolute paths the dependency may be found at, in the order the
+    /// loader would try them. These are tried before falling back to
+    /// matching on the file name alone.
+    pub paths: Vec<String>,
 
-    fn try_from(value: DirEntry) -> Result<Self, Self::Error> {
-        let file = match fs::read(value.path()) {
-            Ok(f) => f,
-            Err(e) => return Err(e.into()),
+    /// Symbols the node imports from this dependency, where the format
+    /// records them per dependency.
+    pub symbols: Vec<String>,
+}
+
+impl Dependency {
+    pub fn new(name: &str, kind: EdgeType) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            paths: vec![],
+            symbols: vec![],
+        }
+    }
+
+    pub fn with_paths(mut self, paths: Vec<String>) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    /// The final path component of the dependency name.
+    pub fn file_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+}
+
+/// EdgeType describes why one node depends upon another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeType {
+    /// DT_NEEDED, or the equivalent import on non-ELF formats.
+    Needed,
+    /// DT_FILTER, the node is a standard filter over the target.
+    Filter,
+    /// DT_AUXILIARY, the node is an auxiliary filter over the target.
+    Auxiliary,
+    /// DT_DEPAUDIT, the target is an audit library for the node's dependencies.
+    DepAudit,
+    /// A kernel module softdep=, the target is loaded alongside the node if present.
+    SoftDep,
+    /// LC_LOAD_WEAK_DYLIB, the target is loaded only if it is present.
+    Weak,
+    /// LC_REEXPORT_DYLIB, the node re-exports the symbols of the target.
+    Reexport,
+    /// A PE delay-load import, the target is loaded on first use.
+    DelayLoad,
+    /// A static object needs a symbol exported by the target at link time.
+    LinkTime,
+    /// A JAR manifest Class-Path entry.
+    ClassPath,
+    /// A `requires` directive of a Java module, or a systemd unit `Requires=`.
+    Requires,
+    /// A JAR bundles or loads the target native library through JNI.
+    Jni,
+    /// A systemd unit `Wants=`, the target is started alongside the node.
+    Wants,
+    /// A systemd service runs the target in one of its `Exec` commands, or
+    /// a running process was executed from the target.
+    Exec,
+    /// A running process has the target mapped into its address space.
+    Mapped,
+    /// The target was seen loaded at runtime with dlopen.
+    Dlopen,
+}
+
+impl EdgeType {
+    /// Returns whether the loader maps the target as soon as the node is
+    /// loaded, before any of its code runs.
+    pub fn is_load(&self) -> bool {
+        matches!(
+            self,
+            EdgeType::Needed
+                | EdgeType::Filter
+                | EdgeType::Auxiliary
+                | EdgeType::Weak
+                | EdgeType::Reexport
+        )
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EdgeType::Needed => "needed",
+            EdgeType::Filter => "filter",
+            EdgeType::Auxiliary => "auxiliary",
+            EdgeType::DepAudit => "depaudit",
+            EdgeType::SoftDep => "softdep",
+            EdgeType::Weak => "weak",
+            EdgeType::Reexport => "reexport",
+            EdgeType::DelayLoad => "delay_load",
+            EdgeType::LinkTime => "link_time",
+            EdgeType::ClassPath => "class_path",
+            EdgeType::Requires => "requires",
+            EdgeType::Jni => "jni",
+            EdgeType::Wants => "wants",
+            EdgeType::Exec => "exec",
+            EdgeType::Mapped => "mapped",
+            EdgeType::Dlopen => "dlopen",
+        }
+    }
+
+    pub fn format_graphviz(&self) -> &'static str {
+        match self {
+            EdgeType::Needed => "solid",
+            EdgeType::Filter => "dashed",
+            EdgeType::Auxiliary => "dotted",
+            EdgeType::DepAudit => "bold",
+            EdgeType::SoftDep => "tapered",
+            EdgeType::Weak => "dashed",
+            EdgeType::Reexport => "bold",
+            EdgeType::DelayLoad => "dashed",
+            EdgeType::LinkTime => "dotted",
+            EdgeType::ClassPath => "solid",
+            EdgeType::Requires => "solid",
+            EdgeType::Jni => "bold",
+            EdgeType::Wants => "dashed",
+            EdgeType::Exec => "bold",
+            EdgeType::Mapped => "dotted",
+            EdgeType::Dlopen => "dashed",
+        }
+    }
+}
+
+impl Serialize for EdgeType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// Compute the directories the loader searches for an ELF object's
+/// dependencies from its DT_RUNPATH, or DT_RPATH if there is no runpath,
+/// with `$ORIGIN` expanded to the directory holding the object.
+fn elf_search_dirs(elf: &Elf, absolute_path: &str) -> Vec<String> {
+    let origin = Path::new(absolute_path)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let paths = if elf.runpaths.is_empty() {
+        &elf.rpaths
+    } else {
+        &elf.runpaths
+    };
+
+    paths
+        .iter()
+        .flat_map(|p| p.split(':'))
+        .filter(|d| !d.is_empty())
+        .map(|d| d.replace("${ORIGIN}", &origin).replace("$ORIGIN", &origin))
+        .collect()
+}
+
+/// Collect every loader-level dependency declared in the dynamic section of
+/// an ELF object. DT_NEEDED entries are taken from goblin directly, while the
+/// filter and audit tags hold colon separated lists in the dynamic string table.
+fn elf_dependencies(elf: &Elf, absolute_path: &str) -> Vec<Dependency> {
+    let dirs = elf_search_dirs(elf, absolute_path);
+    let with_search_paths = |d: Dependency| {
+        let paths = dirs
+            .iter()
+            .map(|dir| normalize(&Path::new(dir).join(&d.name)))
+            .collect();
+        d.with_paths(paths)
+    };
+
+    let mut deps: Vec<Dependency> = elf
+        .libraries
+        .iter()
+        .map(|l| with_search_paths(Dependency::new(l, EdgeType::Needed)))
+        .collect();
+
+    if let Some(dynamic) = &elf.dynamic {
+        for d in dynamic.dyns.iter() {
+            let kind = match d.d_tag {
+                DT_FILTER => EdgeType::Filter,
+                DT_AUXILIARY => EdgeType::Auxiliary,
+                DT_DEPAUDIT => EdgeType::DepAudit,
+                _ => continue,
+            };
+
+            if let Some(value) = elf.dynstrtab.get_at(d.d_val as usize) {
+                for name in value.split(':').filter(|n| !n.is_empty()) {
+                    deps.push(with_search_paths(Dependency::new(name, kind)));
+                }
+            }
+        }
+    }
+
+    deps
+}
+
+impl BinNode {
+    /// Create the nodes held within a file on disk. Most files hold a single
+    /// node, but universal Mach-O binaries hold one node per architecture.
+    pub fn from_entry(value: DirEntry) -> Result<Vec<Self>, BingraphError> {
+        Self::from_path(&value.path())
+    }
+
+    /// Create the nodes for the file at the provided path.
+    pub fn from_path(path: &Path) -> Result<Vec<Self>, BingraphError> {
+        let size = fs::metadata(path)?.len();
+        let mut nodes = Self::parse_file(path)?;
+        for node in nodes.iter_mut() {
+            node.size = size;
+        }
+        Ok(nodes)
+    }
+
+    fn parse_file(path: &Path) -> Result<Vec<Self>, BingraphError> {
+        let file = if kmod::is_module_path(path) {
+            kmod::read_module(path)?
+        } else {
+            fs::read(path)?
         };
 
-        match Object::parse(&file) {
-            Ok(obj) => match obj {
-                Object::Elf(elf) => {
-                    let t: NodeType;
-                    if elf.is_lib {
-                        t = NodeType::ELFLibrary;
-                    } else {
-                        t = NodeType::ELFBinary;
-                    }
-
-                    Ok(Self {
-                        name: value.file_name().into_string().unwrap_or_default(),
-                        absolute_path: value
-                            .path()
-                            .into_os_string()
-                            .into_string()
-                            .unwrap_or_default(),
-                        node_type: t,
-                        in_degree: 0,
-                        out_degree: 0,
-                        dependencies: elf.libraries.into_iter().map(String::from).collect(),
-                        betweenness_centrality: None,
-                        katz_centrality: None,
-                        eigen_centrality: None,
-                        closeness_centrality: None,
-                    })
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let absolute_path = path.to_string_lossy().to_string();
+
+        // goblin does not know about WebAssembly or Java archives.
+        if file.starts_with(wasm::MAGIC) {
+            return Ok(vec![wasm::node(name, absolute_path, &file)?]);
+        }
+        if jar::is_jar_path(path) {
+            return Ok(vec![jar::node(name, absolute_path, &file)?]);
+        }
+
+        match Object::parse(&file)? {
+            Object::Elf(elf) => {
+                if let Some(info) = kmod::ModuleInfo::parse(&elf, &file, &name) {
+                    let mut node = Self::new(
+                        info.name().to_string(),
+                        absolute_path,
+                        NodeType::KernelModule,
+                    );
+                    node.dependencies = info.dependencies();
+                    node.module_info = Some(info);
+                    return Ok(vec![node]);
                 }
 
-                Object::PE(pe) => Ok(Self {
-                    name: value.file_name().into_string().unwrap_or_default(),
-                    absolute_path: value
-                        .path()
-                        .into_os_string()
-                        .into_string()
-                        .unwrap_or_default(),
-                    node_type: NodeType::PortableExecutable,
-                    in_degree: 0,
-                    out_degree: 0,
-                    dependencies: pe.libraries.into_iter().map(String::from).collect(),
-                    betweenness_centrality: None,
-                    katz_centrality: None,
-                    eigen_centrality: None,
-                    closeness_centrality: None,
-                }),
-
-                _ => return Err(format!("{:?} is of unknown file type", value.path()).into()),
-            },
-            Err(e) => return Err(e.into()),
+                if elf.header.e_type == ET_REL {
+                    let mut node = Self::new(name.clone(), absolute_path, NodeType::ELFObject);
+                    node.members = vec![archive::object_member(name, &elf)];
+                    return Ok(vec![node]);
+                }
+
+                let t = if elf.is_lib && python::is_extension_name(&name) {
+                    NodeType::PythonExtension
+                } else if elf.is_lib {
+                    NodeType::ELFLibrary
+                } else {
+                    NodeType::ELFBinary
+                };
+
+                let mut node = Self::new(name, absolute_path.clone(), t);
+                node.dependencies = elf_dependencies(&elf, &absolute_path);
+                Ok(vec![node])
+            }
+
+            Object::Archive(ar) => {
+                let mut node = Self::new(name, absolute_path, NodeType::StaticArchive);
+                node.members = archive::archive_members(&ar, &file);
+                Ok(vec![node])
+            }
+
+            Object::PE(p) => Ok(vec![pe::node(name, absolute_path, &file, &p)]),
+
+            Object::Mach(Mach::Binary(bin)) => {
+                Ok(vec![macho::node(name, absolute_path, &file, &bin)?])
+            }
+
+            Object::Mach(Mach::Fat(multi)) => macho::fat_nodes(name, absolute_path, &file, &multi),
+
+            _ => Err(format!("{:?} is of unknown file type", absolute_path).into()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBFILTER: &[u8] = include_bytes!("../tests/fixtures/elf/libfilter.so");
+    const LIBA: &[u8] = include_bytes!("../tests/fixtures/elf/liba.so");
+
+    fn kinds(deps: &[Dependency]) -> Vec<(&str, EdgeType)> {
+        deps.iter().map(|d| (d.name.as_str(), d.kind)).collect()
+    }
+
+    #[test]
+    fn reads_filter_and_audit_tags() {
+        let elf = Elf::parse(LIBFILTER).unwrap();
+        let deps = elf_dependencies(&elf, "/lib/libfilter.so");
+        assert_eq!(
+            kinds(&deps),
+            [
+                ("libb.so", EdgeType::Filter),
+                ("libaux.so", EdgeType::Auxiliary),
+                ("libaudit.so", EdgeType::DepAudit),
+                ("libaudit2.so", EdgeType::DepAudit),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_needed_entries() {
+        let elf = Elf::parse(LIBA).unwrap();
+        let deps = elf_dependencies(&elf, "/lib/liba.so");
+        assert_eq!(kinds(&deps), [("libb.so", EdgeType::Needed)]);
+        // Without a runpath, the default search directories apply later.
+        assert!(deps[0].paths.is_empty());
+    }
+}
@@ -0,0 +1,691 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+
+use crate::errors::BingraphError;
+
+const RPMTAG_NAME: u32 = 1000;
+const RPMTAG_VERSION: u32 = 1001;
+const RPMTAG_RELEASE: u32 = 1002;
+const RPMTAG_ARCH: u32 = 1022;
+const RPMTAG_PROVIDENAME: u32 = 1047;
+const RPMTAG_REQUIRENAME: u32 = 1049;
+const RPMTAG_DIRINDEXES: u32 = 1116;
+const RPMTAG_BASENAMES: u32 = 1117;
+const RPMTAG_DIRNAMES: u32 = 1118;
+
+const RPM_INT32_TYPE: u32 = 4;
+const RPM_STRING_TYPE: u32 = 6;
+const RPM_STRING_ARRAY_TYPE: u32 = 8;
+const RPM_I18NSTRING_TYPE: u32 = 9;
+
+/// A Package is an installed distribution package owning files on disk.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct Package {
+    name: String,
+    version: String,
+    arch: String,
+
+    /// Names of the packages, capabilities or files this package requires.
+    #[serde(skip)]
+    depends: Vec<String>,
+
+    /// Virtual package names or capabilities this package provides.
+    #[serde(skip)]
+    provides: Vec<String>,
+}
+
+impl Package {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn arch(&self) -> &str {
+        &self.arch
+    }
+}
+
+/// Strip any version constraint from a dependency or provides entry.
+fn requirement_name(entry: &str) -> String {
+    let entry = entry.split_whitespace().next().unwrap_or_default();
+    entry
+        .split(['<', '>', '=', '~'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Split a dpkg relationship field into package names, keeping every
+/// alternative and dropping version constraints and architecture qualifiers.
+fn dpkg_relations(field: &str) -> Vec<String> {
+    field
+        .split([',', '|'])
+        .map(|r| requirement_name(r.split(':').next().unwrap_or_default()))
+        .filter(|r| !r.is_empty())
+        .collect()
+}
+
+/// A PackageDb maps files onto the installed packages that own them, read
+/// from whichever package manager databases exist under a root directory.
+#[derive(Debug, Default)]
+pub struct PackageDb {
+    root: String,
+    packages: Vec<Package>,
+    owners: HashMap<String, usize>,
+    by_name: HashMap<String, usize>,
+    by_provides: HashMap<String, Vec<usize>>,
+}
+
+impl PackageDb {
+    /// Read every supported package database found under `root`.
+    pub fn load(root: &str) -> Result<Self, BingraphError> {
+        let mut db = Self {
+            root: root.trim_end_matches('/').to_string(),
+            ..Default::default()
+        };
+
+        db.load_dpkg()?;
+        db.load_rpm()?;
+        db.load_pacman()?;
+        db.load_apk()?;
+
+        println!(
+            "found {} packages owning {} files under {}",
+            db.packages.len(),
+            db.owners.len(),
+            root
+        );
+
+        Ok(db)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+
+    fn path(&self, path: &str) -> String {
+        format!("{}{}", self.root, path)
+    }
+
+    fn add(&mut self, package: Package, files: impl IntoIterator<Item = String>) {
+        let idx = self.packages.len();
+        self.by_name.insert(package.name.clone(), idx);
+        for p in package.provides.iter() {
+            self.by_provides.entry(p.clone()).or_default().push(idx);
+        }
+        self.packages.push(package);
+        for f in files {
+            self.owners.insert(f, idx);
+        }
+    }
+
+    /// Find the package owning an absolute path below the root. Paths are
+    /// also tried with and without a leading `/usr`, as merged-usr systems
+    /// record files under either location. Symlinks, such as those managed
+    /// by alternatives, are credited to the package owning their target, as
+    /// that is whose contents the node was created from.
+    pub fn owner(&self, path: &str) -> Option<&Package> {
+        fs::canonicalize(path)
+            .ok()
+            .and_then(|target| self.owner_of(&target.to_string_lossy()))
+            .or_else(|| self.owner_of(path))
+    }
+
+    /// Resolve the declared dependencies of a package to the names of the
+    /// installed packages satisfying them, whether by name, by a provided
+    /// capability or by owning a required file.
+    pub fn declared_dependencies(&self, package: &str) -> BTreeSet<String> {
+        let Some(idx) = self.by_name.get(package) else {
+            return BTreeSet::new();
+        };
+
+        let mut declared: BTreeSet<String> = BTreeSet::new();
+        for dep in self.packages[*idx].depends.iter() {
+            if self.by_name.contains_key(dep) {
+                declared.insert(dep.clone());
+            }
+            for p in self.by_provides.get(dep).into_iter().flatten() {
+                declared.insert(self.packages[*p].name.clone());
+            }
+            if dep.starts_with('/') {
+                if let Some(owner) = self.owner_of(dep) {
+                    declared.insert(owner.name.clone());
+                }
+            }
+        }
+
+        declared.remove(package);
+        declared
+    }
+
+    fn owner_of(&self, path: &str) -> Option<&Package> {
+        let path = path.strip_prefix(&self.root).unwrap_or(path);
+
+        let mut candidates = vec![path.to_string()];
+        match path.strip_prefix("/usr") {
+            Some(p) => candidates.push(p.to_string()),
+            None => candidates.push(format!("/usr{}", path)),
+        }
+
+        candidates
+            .iter()
+            .find_map(|c| self.owners.get(c))
+            .map(|idx| &self.packages[*idx])
+    }
+
+    /// Read `/var/lib/dpkg/status` for the installed packages and their
+    /// `info/<package>.list` files for their contents.
+    fn load_dpkg(&mut self) -> Result<(), BingraphError> {
+        let Ok(status) = fs::read_to_string(self.path("/var/lib/dpkg/status")) else {
+            return Ok(());
+        };
+
+        for stanza in status.split("\n\n") {
+            let mut package = Package::default();
+            let mut installed = false;
+            for line in stanza.lines() {
+                let Some((k, v)) = line.split_once(": ") else {
+                    continue;
+                };
+                match k {
+                    "Package" => package.name = v.to_string(),
+                    "Version" => package.version = v.to_string(),
+                    "Architecture" => package.arch = v.to_string(),
+                    "Status" => installed = v.ends_with(" installed"),
+                    "Depends" | "Pre-Depends" => package.depends.extend(dpkg_relations(v)),
+                    "Provides" => package.provides.extend(dpkg_relations(v)),
+                    _ => {}
+                }
+            }
+
+            if !installed || package.name.is_empty() {
+                continue;
+            }
+
+            // Multi-arch packages qualify their list with the architecture.
+            let info = self.path("/var/lib/dpkg/info");
+            let list =
+                fs::read_to_string(format!("{}/{}:{}.list", info, package.name, package.arch))
+                    .or_else(|_| fs::read_to_string(format!("{}/{}.list", info, package.name)))
+                    .unwrap_or_default();
+
+            self.add(package, list.lines().map(String::from));
+        }
+
+        Ok(())
+    }
+
+    /// Read the sqlite rpm database, decoding the header blob of every package.
+    fn load_rpm(&mut self) -> Result<(), BingraphError> {
+        let path = self.path("/var/lib/rpm/rpmdb.sqlite");
+        if !Path::new(&path).exists() {
+            if Path::new(&self.path("/var/lib/rpm/Packages")).exists() {
+                println!("berkeley db rpm databases are not supported, skipping");
+            }
+            return Ok(());
+        }
+
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut stmt = conn.prepare("SELECT blob FROM Packages")?;
+        let blobs = stmt.query_map([], |row| row.get::<_, Vec<u8>>(0))?;
+
+        for blob in blobs {
+            let blob = blob?;
+            let Some(header) = RpmHeader::parse(&blob) else {
+                continue;
+            };
+
+            let release = header.string(RPMTAG_RELEASE).unwrap_or_default();
+            let package = Package {
+                name: header.string(RPMTAG_NAME).unwrap_or_default(),
+                version: format!(
+                    "{}-{}",
+                    header.string(RPMTAG_VERSION).unwrap_or_default(),
+                    release
+                ),
+                arch: header.string(RPMTAG_ARCH).unwrap_or_default(),
+                depends: header.strings(RPMTAG_REQUIRENAME),
+                provides: header.strings(RPMTAG_PROVIDENAME),
+            };
+
+            let dirs = header.strings(RPMTAG_DIRNAMES);
+            let files: Vec<String> = header
+                .strings(RPMTAG_BASENAMES)
+                .into_iter()
+                .zip(header.int32s(RPMTAG_DIRINDEXES))
+                .filter_map(|(base, idx)| Some(format!("{}{}", dirs.get(idx as usize)?, base)))
+                .collect();
+
+            self.add(package, files);
+        }
+
+        Ok(())
+    }
+
+    /// Read the `desc` and `files` entries of every package in the pacman
+    /// local database.
+    fn load_pacman(&mut self) -> Result<(), BingraphError> {
+        let Ok(entries) = fs::read_dir(self.path("/var/lib/pacman/local")) else {
+            return Ok(());
+        };
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let dir = entry.path();
+            let Ok(desc) = fs::read_to_string(dir.join("desc")) else {
+                continue;
+            };
+
+            let sections = pacman_sections(&desc);
+            let first = |k: &str| {
+                sections
+                    .get(k)
+                    .and_then(|v| v.first())
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            let names = |k: &str| -> Vec<String> {
+                sections
+                    .get(k)
+                    .into_iter()
+                    .flatten()
+                    .map(|d| requirement_name(d))
+                    .collect()
+            };
+            let package = Package {
+                name: first("NAME"),
+                version: first("VERSION"),
+                arch: first("ARCH"),
+                depends: names("DEPENDS"),
+                provides: names("PROVIDES"),
+            };
+
+            let files = fs::read_to_string(dir.join("files")).unwrap_or_default();
+            let files = pacman_sections(&files)
+                .remove("FILES")
+                .unwrap_or_default()
+                .into_iter()
+                .map(|f| format!("/{}", f.trim_end_matches('/')));
+
+            self.add(package, files);
+        }
+
+        Ok(())
+    }
+
+    /// Read the apk installed database, where each package is a block of
+    /// single letter fields and file names follow the directory they are in.
+    fn load_apk(&mut self) -> Result<(), BingraphError> {
+        let Ok(installed) = fs::read_to_string(self.path("/lib/apk/db/installed")) else {
+            return Ok(());
+        };
+
+        for block in installed.split("\n\n") {
+            let mut package = Package::default();
+            let mut files: Vec<String> = vec![];
+            let mut dir = String::new();
+
+            for line in block.lines() {
+                let Some((k, v)) = line.split_once(':') else {
+                    continue;
+                };
+                match k {
+                    "P" => package.name = v.to_string(),
+                    "V" => package.version = v.to_string(),
+                    "A" => package.arch = v.to_string(),
+                    "D" => package.depends.extend(
+                        v.split_whitespace()
+                            .filter(|d| !d.starts_with('!'))
+                            .map(requirement_name),
+                    ),
+                    "p" => package
+                        .provides
+                        .extend(v.split_whitespace().map(requirement_name)),
+                    "F" => dir = v.to_string(),
+                    "R" if dir.is_empty() => files.push(format!("/{}", v)),
+                    "R" => files.push(format!("/{}/{}", dir, v)),
+                    _ => {}
+                }
+            }
+
+            if !package.name.is_empty() {
+                self.add(package, files);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Split a pacman database file into its `%SECTION%` headed value lists.
+fn pacman_sections(contents: &str) -> HashMap<String, Vec<String>> {
+    let mut sections: HashMap<String, Vec<String>> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for line in contents.lines() {
+        if let Some(name) = line.strip_prefix('%').and_then(|l| l.strip_suffix('%')) {
+            current = Some(name.to_string());
+            sections.entry(name.to_string()).or_default();
+        } else if line.is_empty() {
+            current = None;
+        } else if let Some(ref name) = current {
+            sections
+                .entry(name.clone())
+                .or_default()
+                .push(line.to_string());
+        }
+    }
+
+    sections
+}
+
+/// A minimal reader over an rpm header blob as stored in the rpm database:
+/// an index of tagged entries followed by the data store they point into.
+struct RpmHeader<'a> {
+    /// Tag, type, offset and count of every index entry.
+    entries: Vec<(u32, u32, usize, usize)>,
+    store: &'a [u8],
+}
+
+fn be32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+impl<'a> RpmHeader<'a> {
+    fn parse(blob: &'a [u8]) -> Option<Self> {
+        let il = be32(blob, 0)? as usize;
+        let dl = be32(blob, 4)? as usize;
+
+        // Check the counts against the blob before trusting them to size
+        // anything, so a corrupt row cannot ask for more than it holds.
+        let start = il.checked_mul(16)?.checked_add(8)?;
+        let store = blob.get(start..start.checked_add(dl)?)?;
+
+        let mut entries = Vec::with_capacity(il);
+        for i in 0..il {
+            let offset = 8 + i * 16;
+            entries.push((
+                be32(blob, offset)?,
+                be32(blob, offset + 4)?,
+                be32(blob, offset + 8)? as usize,
+                be32(blob, offset + 12)? as usize,
+            ));
+        }
+
+        Some(Self { entries, store })
+    }
+
+    fn entry(&self, tag: u32) -> Option<(u32, usize, usize)> {
+        self.entries
+            .iter()
+            .find(|e| e.0 == tag)
+            .map(|e| (e.1, e.2, e.3))
+    }
+
+    fn strings(&self, tag: u32) -> Vec<String> {
+        let Some((kind, offset, count)) = self.entry(tag) else {
+            return vec![];
+        };
+        if !matches!(
+            kind,
+            RPM_STRING_TYPE | RPM_STRING_ARRAY_TYPE | RPM_I18NSTRING_TYPE
+        ) {
+            return vec![];
+        }
+
+        self.store
+            .get(offset..)
+            .unwrap_or_default()
+            .split(|c| *c == 0)
+            .take(count)
+            .map(|s| String::from_utf8_lossy(s).to_string())
+            .collect()
+    }
+
+    fn string(&self, tag: u32) -> Option<String> {
+        self.strings(tag).into_iter().next()
+    }
+
+    fn int32s(&self, tag: u32) -> Vec<u32> {
+        let Some((kind, offset, count)) = self.entry(tag) else {
+            return vec![];
+        };
+        if kind != RPM_INT32_TYPE {
+            return vec![];
+        }
+
+        (0..count)
+            .map_while(|i| be32(self.store, offset + i * 4))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create an empty scan root unique to the test.
+    fn root(name: &str) -> String {
+        let root = std::env::temp_dir().join(format!("bingraph-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root.to_string_lossy().to_string()
+    }
+
+    fn write(root: &str, path: &str, contents: &[u8]) {
+        let path = format!("{}{}", root, path);
+        fs::create_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    /// Build an rpm header blob from string, string array and int32 entries.
+    fn rpm_header(entries: &[(u32, u32, Vec<&str>, Vec<u32>)]) -> Vec<u8> {
+        let mut index: Vec<u8> = vec![];
+        let mut store: Vec<u8> = vec![];
+        for (tag, kind, strings, ints) in entries {
+            if *kind == RPM_INT32_TYPE {
+                store.resize(store.len().next_multiple_of(4), 0);
+            }
+            let count = strings.len().max(ints.len()) as u32;
+            for v in [*tag, *kind, store.len() as u32, count] {
+                index.extend(v.to_be_bytes());
+            }
+            for s in strings {
+                store.extend(s.as_bytes());
+                store.push(0);
+            }
+            for i in ints {
+                store.extend(i.to_be_bytes());
+            }
+        }
+
+        let mut blob = (entries.len() as u32).to_be_bytes().to_vec();
+        blob.extend((store.len() as u32).to_be_bytes());
+        blob.extend(index);
+        blob.extend(store);
+        blob
+    }
+
+    fn bash_header() -> Vec<u8> {
+        rpm_header(&[
+            (RPMTAG_NAME, RPM_STRING_TYPE, vec!["bash"], vec![]),
+            (RPMTAG_VERSION, RPM_STRING_TYPE, vec!["5.2.15"], vec![]),
+            (RPMTAG_RELEASE, RPM_STRING_TYPE, vec!["3.fc39"], vec![]),
+            (RPMTAG_ARCH, RPM_STRING_TYPE, vec!["x86_64"], vec![]),
+            (
+                RPMTAG_REQUIRENAME,
+                RPM_STRING_ARRAY_TYPE,
+                vec!["libc.so.6()(64bit)", "filesystem >= 3", "/usr/bin/sh"],
+                vec![],
+            ),
+            (RPMTAG_DIRINDEXES, RPM_INT32_TYPE, vec![], vec![0, 1]),
+            (
+                RPMTAG_BASENAMES,
+                RPM_STRING_ARRAY_TYPE,
+                vec!["bash", "bash.1.gz"],
+                vec![],
+            ),
+            (
+                RPMTAG_DIRNAMES,
+                RPM_STRING_ARRAY_TYPE,
+                vec!["/usr/bin/", "/usr/share/man/man1/"],
+                vec![],
+            ),
+        ])
+    }
+
+    #[test]
+    fn rpm_header_entries() {
+        let blob = bash_header();
+        let header = RpmHeader::parse(&blob).unwrap();
+
+        assert_eq!(header.string(RPMTAG_NAME).as_deref(), Some("bash"));
+        assert_eq!(header.strings(RPMTAG_REQUIRENAME).len(), 3);
+        assert_eq!(header.strings(RPMTAG_BASENAMES), vec!["bash", "bash.1.gz"]);
+        assert_eq!(header.int32s(RPMTAG_DIRINDEXES), vec![0, 1]);
+        // Reading an entry as the wrong type yields nothing.
+        assert!(header.int32s(RPMTAG_NAME).is_empty());
+        assert!(header.strings(RPMTAG_DIRINDEXES).is_empty());
+        assert!(header.string(RPMTAG_PROVIDENAME).is_none());
+        assert!(RpmHeader::parse(&blob[..blob.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn rpm_header_counts_beyond_blob() {
+        // An index count far beyond the blob is rejected before allocating.
+        let mut blob = u32::MAX.to_be_bytes().to_vec();
+        blob.extend(0u32.to_be_bytes());
+        assert!(RpmHeader::parse(&blob).is_none());
+
+        let mut blob = 0u32.to_be_bytes().to_vec();
+        blob.extend(u32::MAX.to_be_bytes());
+        assert!(RpmHeader::parse(&blob).is_none());
+    }
+
+    #[test]
+    fn rpm_database() {
+        let root = root("rpmdb");
+        fs::create_dir_all(format!("{}/var/lib/rpm", root)).unwrap();
+        let conn = Connection::open(format!("{}/var/lib/rpm/rpmdb.sqlite", root)).unwrap();
+        conn.execute(
+            "CREATE TABLE Packages (hnum INTEGER PRIMARY KEY, blob BLOB)",
+            [],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO Packages (blob) VALUES (?1)", [bash_header()])
+            .unwrap();
+        drop(conn);
+
+        let db = PackageDb::load(&root).unwrap();
+        let owner = db.owner(&format!("{}/usr/bin/bash", root)).unwrap();
+        assert_eq!(owner.name(), "bash");
+        assert_eq!(owner.version(), "5.2.15-3.fc39");
+        assert_eq!(owner.arch(), "x86_64");
+        // Merged-usr systems reach the same file without the /usr prefix.
+        assert!(db.owner(&format!("{}/bin/bash", root)).is_some());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn dpkg_database() {
+        let root = root("dpkg");
+        write(
+            &root,
+            "/var/lib/dpkg/status",
+            b"Package: libc6\nStatus: install ok installed\nArchitecture: amd64\nVersion: 2.36\nProvides: libc-dev-bin\n\nPackage: curl\nStatus: install ok installed\nArchitecture: amd64\nVersion: 7.88\nDepends: libc6 (>= 2.34), libcurl4 (= 7.88) | libcurl3:any\n\nPackage: removed\nStatus: deinstall ok config-files\nArchitecture: amd64\nVersion: 1\n",
+        );
+        write(
+            &root,
+            "/var/lib/dpkg/info/libc6:amd64.list",
+            b"/lib/x86_64-linux-gnu/libc.so.6\n",
+        );
+        write(&root, "/var/lib/dpkg/info/curl.list", b"/usr/bin/curl\n");
+
+        let db = PackageDb::load(&root).unwrap();
+        assert_eq!(db.packages.len(), 2);
+        let owner = |p: &str| db.owner(&format!("{}{}", root, p)).map(|o| o.name());
+        assert_eq!(owner("/usr/lib/x86_64-linux-gnu/libc.so.6"), Some("libc6"));
+        assert_eq!(owner("/usr/bin/curl"), Some("curl"));
+        assert_eq!(owner("/usr/bin/wget"), None);
+
+        assert_eq!(
+            db.declared_dependencies("curl")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["libc6"]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn pacman_and_apk_databases() {
+        let root = root("pacman-apk");
+        write(
+            &root,
+            "/var/lib/pacman/local/zlib-1.3-1/desc",
+            b"%NAME%\nzlib\n\n%VERSION%\n1:1.3-1\n\n%ARCH%\nx86_64\n\n%DEPENDS%\nglibc>=2.38\n\n",
+        );
+        write(
+            &root,
+            "/var/lib/pacman/local/zlib-1.3-1/files",
+            b"%FILES%\nusr/\nusr/lib/\nusr/lib/libz.so.1\n\n",
+        );
+        write(
+            &root,
+            "/lib/apk/db/installed",
+            b"P:musl\nV:1.2.4-r2\nA:x86_64\nD:!conflict so:libc.musl\np:so:libc.musl-x86_64.so.1=1\nR:.PKGINFO\nF:lib\nR:ld-musl-x86_64.so.1\n\nP:busybox\nV:1.36\nA:x86_64\nD:so:libc.musl-x86_64.so.1\nF:bin\nR:busybox\n",
+        );
+
+        let db = PackageDb::load(&root).unwrap();
+        let owner = |p: &str| db.owner(&format!("{}{}", root, p)).map(|o| o.name());
+        assert_eq!(owner("/usr/lib/libz.so.1"), Some("zlib"));
+        assert_eq!(owner("/usr/lib"), Some("zlib"));
+        assert_eq!(owner("/lib/ld-musl-x86_64.so.1"), Some("musl"));
+        assert_eq!(owner("/bin/busybox"), Some("busybox"));
+
+        let busybox = db.declared_dependencies("busybox");
+        assert_eq!(busybox.into_iter().collect::<Vec<_>>(), vec!["musl"]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn requirement_names() {
+        assert_eq!(requirement_name("glibc>=2.38"), "glibc");
+        assert_eq!(requirement_name("filesystem >= 3"), "filesystem");
+        assert_eq!(
+            dpkg_relations("libc6 (>= 2.34), libcurl4 | libcurl3:any"),
+            vec!["libc6", "libcurl4", "libcurl3"]
+        );
+    }
+}
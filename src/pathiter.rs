@@ -16,33 +16,75 @@
 *	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
  */
 
-use std::fs::{self, DirEntry, ReadDir};
+use std::{
+    fs::{self, DirEntry, ReadDir},
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+
+/// Lexically normalize a path, collapsing `.` and `..` components, since
+/// relative references such as `@loader_path/../Frameworks/...` are common.
+pub fn normalize(path: &Path) -> String {
+    let mut out = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            c => out.push(c),
+        }
+    }
+    out.to_string_lossy().to_string()
+}
 
 /// Implements an iterator to iterate through all files
-/// that are found within the provided system path.
+/// that are found within the provided system path. Directories are only
+/// opened once the iterator reaches them, so that walking a deep tree keeps
+/// a single directory handle open at a time.
 pub struct PathIterator {
-    directories: Vec<ReadDir>,
+    directories: Vec<PathBuf>,
     curr_iter: Option<ReadDir>,
+
+    /// Whether subdirectories should be descended into as well.
+    recursive: bool,
 }
 
 impl PathIterator {
     pub fn new(path: &str) -> Self {
-        let mut s = Self {
-            directories: vec![],
-            curr_iter: None,
-        };
+        Self::with_recursion(path, false)
+    }
 
-        for subpath in path.split(":") {
-            if let Ok(dir) = fs::read_dir(subpath) {
-                s.directories.push(dir);
-            }
-        }
+    /// Create an iterator that also walks every subdirectory of the provided
+    /// system path. Symlinks to directories are not followed.
+    pub fn recursive(path: &str) -> Self {
+        Self::with_recursion(path, true)
+    }
 
-        if let Some(curr) = s.directories.pop() {
-            s.curr_iter = Some(curr);
+    fn with_recursion(path: &str, recursive: bool) -> Self {
+        Self {
+            directories: path
+                .split(':')
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+                .collect(),
+            curr_iter: None,
+            recursive,
         }
+    }
 
-        s
+    /// Open the next pending directory, reporting any that cannot be read.
+    /// Missing entries are skipped quietly, as search paths commonly name
+    /// directories that do not exist on every system.
+    fn open_next(&mut self) -> Option<ReadDir> {
+        while let Some(dir) = self.directories.pop() {
+            match fs::read_dir(&dir) {
+                Ok(entries) => return Some(entries),
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => println!("unable to read directory {:?}: {}", dir, e),
+            }
+        }
+        None
     }
 }
 
@@ -50,35 +92,71 @@ impl Iterator for PathIterator {
     type Item = DirEntry;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match &mut self.curr_iter {
-            Some(i) => match i.next() {
-                Some(dir) => match dir {
-                    Ok(entry) => match entry.file_type() {
-                        Ok(info) => {
-                            if info.is_dir() {
-                                self.next()
-                            } else {
-                                Some(entry)
-                            }
-                        }
-                        Err(e) => {
-                            println!("unable to get file type for path {:?}: {}", entry, e);
-                            self.next()
-                        }
-                    },
-                    Err(_) => self.next(),
-                },
+        loop {
+            if self.curr_iter.is_none() {
+                self.curr_iter = Some(self.open_next()?);
+            }
+
+            let entry = match self.curr_iter.as_mut()?.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(e)) => {
+                    println!("unable to read directory entry: {}", e);
+                    continue;
+                }
                 None => {
-                    if let Some(next_dir) = self.directories.pop() {
-                        self.curr_iter = Some(next_dir);
-                        self.next()
-                    } else {
-                        self.curr_iter = None;
-                        self.next()
+                    self.curr_iter = None;
+                    continue;
+                }
+            };
+
+            match entry.file_type() {
+                Ok(info) if info.is_dir() => {
+                    if self.recursive {
+                        self.directories.push(entry.path());
                     }
                 }
-            },
-            None => None,
+                Ok(_) => return Some(entry),
+                Err(e) => println!("unable to get file type for path {:?}: {}", entry, e),
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_collapses_relative_components() {
+        assert_eq!(
+            normalize(Path::new("/a/b/./../Frameworks/c")),
+            "/a/Frameworks/c"
+        );
+    }
+
+    #[test]
+    fn recursion_is_opt_in() {
+        let root = std::env::temp_dir().join(format!("bingraph-pathiter-{}", std::process::id()));
+        fs::create_dir_all(root.join("sub/deeper")).unwrap();
+        fs::write(root.join("top"), b"").unwrap();
+        fs::write(root.join("sub/mid"), b"").unwrap();
+        fs::write(root.join("sub/deeper/leaf"), b"").unwrap();
+
+        let path = format!("{}::/nonexistent/bingraph", root.display());
+        let names = |it: PathIterator| {
+            let mut names: Vec<String> = it
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(PathIterator::new(&path)), vec!["top"]);
+        assert_eq!(
+            names(PathIterator::recursive(&path)),
+            vec!["leaf", "mid", "top"]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
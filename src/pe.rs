@@ -0,0 +1,532 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use goblin::pe::{
+    header::{COFF_MACHINE_ARM64, COFF_MACHINE_X86, COFF_MACHINE_X86_64},
+    options::ParseOptions,
+    utils::find_offset,
+    PE,
+};
+
+use crate::{
+    node::{BinNode, Dependency, EdgeType, NodeType},
+    winreg::{Hive, RegFile, RegValue},
+};
+
+/// Size of an IMAGE_DELAYLOAD_DESCRIPTOR entry.
+const SIZEOF_DELAY_DESCRIPTOR: usize = 32;
+
+/// The KnownDLLs of a default Windows 10 22H2 x64 installation, the value
+/// names under `HKLM\SYSTEM\CurrentControlSet\Control\Session Manager\KnownDLLs`.
+/// These are always loaded from the system directory, regardless of the
+/// application directory. Used unless the scanned root holds a SYSTEM hive
+/// or is a Wine prefix.
+const KNOWN_DLLS: [&str; 30] = [
+    "advapi32.dll",
+    "clbcatq.dll",
+    "combase.dll",
+    "comdlg32.dll",
+    "coml2.dll",
+    "difxapi.dll",
+    "gdi32.dll",
+    "gdiplus.dll",
+    "imagehlp.dll",
+    "imm32.dll",
+    "kernel32.dll",
+    "msctf.dll",
+    "msvcrt.dll",
+    "normaliz.dll",
+    "nsi.dll",
+    "ntdll.dll",
+    "ole32.dll",
+    "oleaut32.dll",
+    "psapi.dll",
+    "rpcrt4.dll",
+    "sechost.dll",
+    "setupapi.dll",
+    "shcore.dll",
+    "shell32.dll",
+    "shlwapi.dll",
+    "user32.dll",
+    "wldap32.dll",
+    "wow64.dll",
+    "wow64win.dll",
+    "ws2_32.dll",
+];
+
+/// Contract name prefixes mapped onto the DLL that hosts them, summarizing
+/// the `apisetschema.dll` of the same Windows 10 release. More specific
+/// prefixes must come first. Used unless the scanned root holds the schema.
+const API_SET_HOSTS: [(&str, &str); 16] = [
+    ("api-ms-win-crt-", "ucrtbase.dll"),
+    ("api-ms-win-core-com-", "combase.dll"),
+    ("api-ms-win-core-winrt-", "combase.dll"),
+    ("api-ms-win-core-rtlsupport-", "ntdll.dll"),
+    ("api-ms-win-core-kernel32-", "kernel32.dll"),
+    ("api-ms-win-core-", "kernelbase.dll"),
+    ("api-ms-win-security-lsalookup-", "sechost.dll"),
+    ("api-ms-win-security-", "kernelbase.dll"),
+    ("api-ms-win-eventing-", "kernelbase.dll"),
+    ("api-ms-win-service-", "sechost.dll"),
+    ("api-ms-win-shcore-", "shcore.dll"),
+    ("api-ms-win-gdi-", "gdi32.dll"),
+    ("api-ms-win-ntuser-", "user32.dll"),
+    ("api-ms-win-rtcore-ntuser-", "user32.dll"),
+    ("ext-ms-win-ntuser-", "user32.dll"),
+    ("ext-ms-win-gdi-", "gdi32.dll"),
+];
+
+/// The only API set schema layout understood, used since Windows 10.
+const API_SET_SCHEMA_VERSION: u32 = 6;
+const SIZEOF_API_SET_NAMESPACE_ENTRY: usize = 24;
+const SIZEOF_API_SET_VALUE_ENTRY: usize = 20;
+
+/// The tables the Windows loader consults before searching for a DLL,
+/// either the built-in defaults or those read from the scanned root.
+pub struct LoaderTables {
+    known_dlls: Vec<String>,
+
+    /// Hosts keyed by contract name up to its last hyphen, when read from
+    /// an `apisetschema.dll`. Empty when using the built-in prefixes.
+    api_sets: HashMap<String, String>,
+}
+
+impl Default for LoaderTables {
+    fn default() -> Self {
+        Self {
+            known_dlls: KNOWN_DLLS.iter().map(|d| d.to_string()).collect(),
+            api_sets: HashMap::new(),
+        }
+    }
+}
+
+/// Find a path below the root, matching each component case-insensitively
+/// as Windows images mounted on other systems keep their original casing.
+fn find_insensitive(root: &Path, components: &[&str]) -> Option<PathBuf> {
+    components.iter().try_fold(root.to_path_buf(), |dir, c| {
+        fs::read_dir(&dir)
+            .ok()?
+            .flatten()
+            .find(|e| e.file_name().to_string_lossy().eq_ignore_ascii_case(c))
+            .map(|e| e.path())
+    })
+}
+
+/// Find the Windows directory of an image mounted at the root, or of a Wine
+/// prefix when the root is the prefix itself.
+fn windows_dir(root: &Path) -> Option<PathBuf> {
+    find_insensitive(root, &["Windows"]).or_else(|| find_insensitive(root, &["drive_c", "Windows"]))
+}
+
+/// Find the `system.reg` of a Wine prefix, when the root is either the
+/// prefix or its `drive_c`.
+fn wine_registry(root: &Path) -> Option<PathBuf> {
+    [Some(root), root.parent()]
+        .into_iter()
+        .flatten()
+        .map(|d| d.join("system.reg"))
+        .find(|p| p.is_file())
+}
+
+/// Returns the name of the control set in use, from the values of the
+/// `Select` key.
+fn current_control_set(select: Vec<(String, RegValue)>) -> Option<String> {
+    select.into_iter().find_map(|(n, v)| match v {
+        RegValue::Dword(c) if n.eq_ignore_ascii_case("Current") => {
+            Some(format!("ControlSet{:03}", c))
+        }
+        _ => None,
+    })
+}
+
+/// Keep the values of the KnownDLLs key that name a DLL, lowercased.
+/// DllDirectory and DllDirectory32 name the system directories instead.
+fn known_dll_values(values: Vec<(String, RegValue)>) -> Option<Vec<String>> {
+    let dlls: Vec<String> = values
+        .into_iter()
+        .filter_map(|(_, v)| match v {
+            RegValue::String(d) if d.to_lowercase().ends_with(".dll") => Some(d.to_lowercase()),
+            _ => None,
+        })
+        .collect();
+    (!dlls.is_empty()).then_some(dlls)
+}
+
+/// Read the KnownDLLs of the current control set out of a SYSTEM hive.
+fn read_known_dlls(hive: &Hive) -> Option<Vec<String>> {
+    let control_set = current_control_set(hive.values(hive.key(&["Select"])?))?;
+    let key = hive.key(&[&control_set, "Control", "Session Manager", "KnownDLLs"])?;
+    known_dll_values(hive.values(key))
+}
+
+/// Read the KnownDLLs out of the `system.reg` of a Wine prefix. Wine keeps
+/// `CurrentControlSet` as a link to the control set holding the keys, which
+/// is the first one unless the `Select` key says otherwise.
+fn read_wine_known_dlls(reg: &RegFile) -> Option<Vec<String>> {
+    let control_set = reg
+        .values(&["System", "Select"])
+        .and_then(current_control_set)
+        .unwrap_or_else(|| "ControlSet001".to_string());
+
+    [control_set.as_str(), "CurrentControlSet"]
+        .iter()
+        .find_map(|cs| reg.values(&["System", cs, "Control", "Session Manager", "KnownDLLs"]))
+        .and_then(known_dll_values)
+}
+
+/// Parse a version 6 API set schema, the contents of the `.apiset` section
+/// of `apisetschema.dll`, into the default host of every contract.
+fn parse_api_set_schema(data: &[u8]) -> Option<HashMap<String, String>> {
+    let u32_at = |o: usize| Some(u32::from_le_bytes(data.get(o..o + 4)?.try_into().ok()?) as usize);
+    let utf16_at = |offset: usize, len: usize| {
+        let units: Vec<u16> = data
+            .get(offset..offset + len)?
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        Some(String::from_utf16_lossy(&units).to_lowercase())
+    };
+
+    if u32_at(0)? != API_SET_SCHEMA_VERSION as usize {
+        return None;
+    }
+
+    let count = u32_at(12)?;
+    let entries = u32_at(16)?;
+
+    let mut hosts: HashMap<String, String> = HashMap::new();
+    for i in 0..count {
+        let entry = entries + i * SIZEOF_API_SET_NAMESPACE_ENTRY;
+        let hashed = utf16_at(u32_at(entry + 4)?, u32_at(entry + 12)?)?;
+        let values = u32_at(entry + 16)?;
+
+        // The default host is the value that is not specific to an importer.
+        let host = (0..u32_at(entry + 20)?)
+            .map(|j| values + j * SIZEOF_API_SET_VALUE_ENTRY)
+            .find(|v| u32_at(v + 8) == Some(0))
+            .and_then(|v| utf16_at(u32_at(v + 12)?, u32_at(v + 16)?));
+
+        if let Some(host) = host.filter(|h| !h.is_empty()) {
+            hosts.insert(hashed, host);
+        }
+    }
+
+    Some(hosts)
+}
+
+/// Read the API set schema out of an `apisetschema.dll`.
+fn read_api_sets(data: &[u8]) -> Option<HashMap<String, String>> {
+    let pe = PE::parse(data).ok()?;
+    let section = pe
+        .sections
+        .iter()
+        .find(|s| s.name().is_ok_and(|n| n == ".apiset"))?;
+    let start = section.pointer_to_raw_data as usize;
+    let end = start + section.size_of_raw_data as usize;
+    parse_api_set_schema(data.get(start..end)?)
+}
+
+fn hashed_api_set_name(name: &str) -> &str {
+    let stem = name.strip_suffix(".dll").unwrap_or(name);
+    stem.rsplit_once('-').map(|(h, _)| h).unwrap_or(stem)
+}
+
+impl LoaderTables {
+    /// Read the KnownDLLs and API set tables of the Windows installation or
+    /// Wine prefix at the root, keeping the built-in ones where it has none.
+    pub fn load(root: &str) -> Self {
+        let mut tables = Self::default();
+        let root = Path::new(root);
+        let windows = windows_dir(root);
+
+        // A mounted image keeps its registry in binary hives, while a Wine
+        // prefix saves it as text next to `drive_c`.
+        let hive = windows
+            .as_ref()
+            .and_then(|w| find_insensitive(w, &["System32", "config", "SYSTEM"]));
+        let known_dlls = match (hive, wine_registry(root)) {
+            (Some(path), _) => Some((Hive::open(&path).map(|h| read_known_dlls(&h)), path)),
+            (None, Some(path)) => {
+                Some((RegFile::open(&path).map(|r| read_wine_known_dlls(&r)), path))
+            }
+            (None, None) => None,
+        };
+        if let Some((result, path)) = known_dlls {
+            match result {
+                Ok(Some(dlls)) => {
+                    println!("read {} KnownDLLs from {:?}", dlls.len(), path);
+                    tables.known_dlls = dlls;
+                }
+                Ok(None) => println!("no KnownDLLs found in {:?}", path),
+                Err(e) => println!("unable to read {:?}: {}", path, e),
+            }
+        }
+
+        let schema = windows
+            .as_ref()
+            .and_then(|w| find_insensitive(w, &["System32", "apisetschema.dll"]));
+        if let Some(path) = schema {
+            match fs::read(&path).map(|d| read_api_sets(&d)) {
+                Ok(Some(hosts)) => {
+                    println!("read {} API sets from {:?}", hosts.len(), path);
+                    tables.api_sets = hosts;
+                }
+                Ok(None) => println!("unsupported API set schema in {:?}", path),
+                Err(e) => println!("unable to read {:?}: {}", path, e),
+            }
+        }
+
+        tables
+    }
+
+    /// Returns whether the lowercased DLL name is a KnownDLL.
+    pub fn is_known_dll(&self, name: &str) -> bool {
+        self.known_dlls.iter().any(|d| d == name)
+    }
+
+    /// Returns the host DLL of a lowercased API set contract name, if it is
+    /// one. Schema entries are matched the way the loader does, on the name
+    /// without its extension up to the last hyphen, which drops the minor
+    /// version.
+    pub fn api_set_host(&self, name: &str) -> Option<&str> {
+        if !self.api_sets.is_empty() {
+            return self
+                .api_sets
+                .get(hashed_api_set_name(name))
+                .map(String::as_str);
+        }
+
+        API_SET_HOSTS
+            .iter()
+            .find(|(prefix, _)| name.starts_with(prefix))
+            .map(|(_, host)| *host)
+    }
+}
+
+/// Returns whether a lowercased path lies within a Windows system directory.
+pub fn is_system_path(path: &str) -> bool {
+    path.contains("/system32/") || path.contains("/syswow64/")
+}
+
+/// Read the names of the DLLs listed in the delay-load import directory.
+/// goblin does not parse this directory, so walk the descriptors by hand.
+fn delay_imports<'a>(data: &'a [u8], pe: &PE) -> Vec<&'a str> {
+    let mut names: Vec<&str> = vec![];
+
+    let Some(opt) = pe.header.optional_header else {
+        return names;
+    };
+    let Some(dir) = opt.data_directories.get_delay_import_descriptor() else {
+        return names;
+    };
+
+    let file_alignment = opt.windows_fields.file_alignment;
+    let opts = ParseOptions::default();
+    let offset_of = |rva: u32| find_offset(rva as usize, &pe.sections, file_alignment, &opts);
+
+    let Some(mut offset) = offset_of(dir.virtual_address) else {
+        return names;
+    };
+
+    while let Some(desc) = data.get(offset..offset + SIZEOF_DELAY_DESCRIPTOR) {
+        let name_rva = u32::from_le_bytes([desc[4], desc[5], desc[6], desc[7]]);
+        if name_rva == 0 {
+            break;
+        }
+
+        if let Some(name) = offset_of(name_rva)
+            .and_then(|o| data.get(o..))
+            .and_then(|b| b.split(|c| *c == 0).next())
+            .and_then(|b| std::str::from_utf8(b).ok())
+        {
+            names.push(name);
+        }
+
+        offset += SIZEOF_DELAY_DESCRIPTOR;
+    }
+
+    names
+}
+
+/// Create a node from a PE image, including its delay-loaded imports.
+pub fn node(name: String, absolute_path: String, data: &[u8], pe: &PE) -> BinNode {
+    let mut deps: Vec<Dependency> = pe
+        .libraries
+        .iter()
+        .map(|l| {
+            let mut dep = Dependency::new(l, EdgeType::Needed);
+            dep.symbols = pe
+                .imports
+                .iter()
+                .filter(|i| i.dll.eq_ignore_ascii_case(l))
+                .map(|i| i.name.to_string())
+                .collect();
+            dep
+        })
+        .collect();
+    deps.extend(
+        delay_imports(data, pe)
+            .into_iter()
+            .map(|l| Dependency::new(l, EdgeType::DelayLoad)),
+    );
+
+    // IMAGE_FILE_DLL in the COFF characteristics tells DLLs apart from
+    // executables, whatever their file extension.
+    let t = if pe.is_lib {
+        NodeType::PortableDll
+    } else {
+        NodeType::PortableExecutable
+    };
+    let mut node = BinNode::new(name, absolute_path, t);
+    node.set_dependencies(deps);
+
+    let arch = match pe.header.coff_header.machine {
+        COFF_MACHINE_X86 => Some("i386"),
+        COFF_MACHINE_X86_64 => Some("x86_64"),
+        COFF_MACHINE_ARM64 => Some("arm64"),
+        _ => None,
+    };
+    if let Some(a) = arch {
+        node.set_arch(a.to_string());
+    }
+
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    /// Build a version 6 schema with one contract per (name, hashed, host).
+    fn schema(contracts: &[(&str, usize, &str)]) -> Vec<u8> {
+        let header = 28;
+        let entries = header;
+        let values = entries + contracts.len() * SIZEOF_API_SET_NAMESPACE_ENTRY;
+        let mut strings = values + contracts.len() * SIZEOF_API_SET_VALUE_ENTRY;
+
+        let mut data: Vec<u8> = vec![];
+        let mut text: Vec<u8> = vec![];
+        for v in [
+            API_SET_SCHEMA_VERSION,
+            0,
+            0,
+            contracts.len() as u32,
+            entries as u32,
+            0,
+            0,
+        ] {
+            data.extend(v.to_le_bytes());
+        }
+
+        let mut value_entries: Vec<u8> = vec![];
+        for (i, (name, hashed, host)) in contracts.iter().enumerate() {
+            let (name, host) = (utf16(name), utf16(host));
+            let name_offset = strings;
+            let host_offset = strings + name.len();
+            strings += name.len() + host.len();
+            text.extend(name.iter().chain(host.iter()));
+
+            let value = values + i * SIZEOF_API_SET_VALUE_ENTRY;
+            for v in [0, name_offset, name.len(), hashed * 2, value, 1] {
+                data.extend((v as u32).to_le_bytes());
+            }
+            for v in [0, 0, 0, host_offset, host.len()] {
+                value_entries.extend((v as u32).to_le_bytes());
+            }
+        }
+
+        data.extend(value_entries);
+        data.extend(text);
+        data
+    }
+
+    #[test]
+    fn api_set_schema() {
+        let data = schema(&[
+            ("api-ms-win-core-synch-l1-2-0", 26, "kernelbase.dll"),
+            ("api-ms-win-crt-math-l1-1-0", 24, "ucrtbase.dll"),
+        ]);
+        let hosts = parse_api_set_schema(&data).unwrap();
+
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(
+            hosts.get(hashed_api_set_name("api-ms-win-core-synch-l1-2-0.dll")),
+            Some(&"kernelbase.dll".to_string())
+        );
+        assert_eq!(
+            hosts.get(hashed_api_set_name("api-ms-win-crt-math-l1-1-1.dll")),
+            Some(&"ucrtbase.dll".to_string())
+        );
+        assert!(parse_api_set_schema(&[2, 0, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn default_tables() {
+        let tables = LoaderTables::default();
+        assert!(tables.is_known_dll("kernel32.dll"));
+        assert!(!tables.is_known_dll("zlib1.dll"));
+        assert_eq!(
+            tables.api_set_host("api-ms-win-crt-runtime-l1-1-0.dll"),
+            Some("ucrtbase.dll")
+        );
+        assert_eq!(
+            tables.api_set_host("api-ms-win-core-com-l1-1-0.dll"),
+            Some("combase.dll")
+        );
+        assert_eq!(tables.api_set_host("zlib1.dll"), None);
+    }
+
+    #[test]
+    fn wine_prefix_tables() {
+        let prefix = std::env::temp_dir().join(format!("bingraph-wine-{}", std::process::id()));
+        let drive_c = prefix.join("drive_c");
+        fs::create_dir_all(drive_c.join("windows/system32")).unwrap();
+        fs::write(
+            prefix.join("system.reg"),
+            concat!(
+                "WINE REGISTRY Version 2\n",
+                ";; All keys relative to \\\\Machine\n",
+                "\n",
+                "[System\\\\ControlSet001\\\\Control\\\\Session Manager\\\\KnownDLLs] 1700000000\n",
+                "#time=1da0000000000000\n",
+                "\"kernel32\"=\"kernel32.dll\"\n",
+                "\"zlib1\"=\"ZLIB1.DLL\"\n",
+                "\"DllDirectory\"=str(2):\"%SystemRoot%\\\\system32\"\n",
+            ),
+        )
+        .unwrap();
+
+        for root in [&prefix, &drive_c] {
+            let tables = LoaderTables::load(root.to_str().unwrap());
+            assert!(tables.is_known_dll("zlib1.dll"));
+            assert!(!tables.is_known_dll("user32.dll"));
+        }
+
+        fs::remove_dir_all(&prefix).unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use rustworkx_core::petgraph::graph::{DiGraph, NodeIndex};
+use serde::Serialize;
+
+use crate::{
+    graph::{BinGraph, Centralities},
+    metrics::{MetricRun, MetricSpec},
+    package::PackageDb,
+};
+
+/// A PackageNode is a package collapsed from every file node it owns.
+#[derive(Debug, Serialize, Clone)]
+pub struct PackageNode {
+    name: String,
+    version: String,
+    arch: String,
+
+    /// Number of file nodes owned by this package.
+    files: u32,
+
+    in_degree: u32,
+    out_degree: u32,
+
+    betweenness_centrality: Option<f64>,
+    katz_centrality: Option<f64>,
+    eigen_centrality: Option<f64>,
+    closeness_centrality: Option<f64>,
+}
+
+/// A PackageEdge links two packages, weighted by the number of file-level
+/// edges between them.
+#[derive(Debug, Serialize, Clone)]
+pub struct PackageEdge {
+    source: String,
+    target: String,
+    weight: u32,
+}
+
+/// A PackageDependencyReport compares the dependencies of a package inferred
+/// from its files against the ones declared in its metadata.
+#[derive(Debug, Serialize)]
+pub struct PackageDependencyReport {
+    package: String,
+
+    /// Packages linked against without being declared as dependencies.
+    undeclared: Vec<String>,
+
+    /// Declared dependencies with files in the graph that nothing links to.
+    unused: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageGraph {
+    nodes: Vec<PackageNode>,
+    edges: Vec<PackageEdge>,
+    dependency_reports: Vec<PackageDependencyReport>,
+
+    num_nodes: u32,
+    num_edges: u32,
+
+    /// File nodes left out of the package graph, as no package owns them.
+    unowned_files: u32,
+
+    /// How each selected centrality was computed.
+    metrics: Vec<MetricRun>,
+}
+
+impl PackageGraph {
+    /// Collapse a file graph with attached packages into the quotient graph
+    /// of packages, and check it against the package metadata.
+    pub fn new(g: &BinGraph, db: &PackageDb, metrics: &[MetricSpec]) -> Self {
+        let mut graph: DiGraph<PackageNode, u32> = DiGraph::new();
+        let mut by_name: HashMap<String, NodeIndex> = HashMap::new();
+
+        // Map every file node onto the node of its package.
+        let mut unowned_files = 0;
+        let members: Vec<Option<NodeIndex>> = g
+            .nodes()
+            .iter()
+            .map(|n| {
+                let Some(p) = n.package() else {
+                    unowned_files += 1;
+                    return None;
+                };
+                let idx = *by_name.entry(p.name().to_string()).or_insert_with(|| {
+                    graph.add_node(PackageNode {
+                        name: p.name().to_string(),
+                        version: p.version().to_string(),
+                        arch: p.arch().to_string(),
+                        files: 0,
+                        in_degree: 0,
+                        out_degree: 0,
+                        betweenness_centrality: None,
+                        katz_centrality: None,
+                        eigen_centrality: None,
+                        closeness_centrality: None,
+                    })
+                });
+                graph[idx].files += 1;
+                Some(idx)
+            })
+            .collect();
+
+        // Count the file links between every pair of distinct packages.
+        let mut weights: BTreeMap<(NodeIndex, NodeIndex), u32> = BTreeMap::new();
+        for e in g.edges() {
+            let (Some(s), Some(t)) = (members[e.source_idx()], members[e.target_idx()]) else {
+                continue;
+            };
+            if s != t {
+                *weights.entry((s, t)).or_default() += 1;
+            }
+        }
+
+        for ((s, t), w) in weights.iter() {
+            graph.add_edge(*s, *t, *w);
+            graph[*s].out_degree += 1;
+            graph[*t].in_degree += 1;
+        }
+
+        let centralities = Centralities::compute(&graph, |w| *w as f64, metrics);
+        for idx in graph.node_indices() {
+            let node = &mut graph[idx];
+            node.betweenness_centrality = centralities.betweenness(idx.index());
+            node.katz_centrality = centralities.katz(idx.index());
+            node.eigen_centrality = centralities.eigen(idx.index());
+            node.closeness_centrality = centralities.closeness(idx.index());
+        }
+
+        // Compare the inferred dependencies with the declared ones. Declared
+        // dependencies on packages without any files in the graph cannot be
+        // judged, so only those with files are reported as unused.
+        println!("comparing inferred and declared package dependencies");
+        let mut dependency_reports: Vec<PackageDependencyReport> = vec![];
+        for idx in graph.node_indices() {
+            let name = &graph[idx].name;
+            let inferred: BTreeSet<String> = graph
+                .neighbors(idx)
+                .map(|n| graph[n].name.clone())
+                .collect();
+            let declared = db.declared_dependencies(name);
+
+            let undeclared: Vec<String> = inferred.difference(&declared).cloned().collect();
+            let unused: Vec<String> = declared
+                .difference(&inferred)
+                .filter(|d| by_name.contains_key(*d))
+                .cloned()
+                .collect();
+
+            if !undeclared.is_empty() || !unused.is_empty() {
+                dependency_reports.push(PackageDependencyReport {
+                    package: name.clone(),
+                    undeclared,
+                    unused,
+                });
+            }
+        }
+        dependency_reports.sort_by(|a, b| a.package.cmp(&b.package));
+
+        for r in dependency_reports.iter() {
+            println!(
+                "{}: undeclared: {:?}, unused: {:?}",
+                r.package, r.undeclared, r.unused
+            );
+        }
+        println!(
+            "{} of {} packages disagree with their declared dependencies",
+            dependency_reports.len(),
+            graph.node_count()
+        );
+
+        let edges = graph
+            .edge_indices()
+            .filter_map(|e| {
+                let (s, t) = graph.edge_endpoints(e)?;
+                Some(PackageEdge {
+                    source: graph[s].name.clone(),
+                    target: graph[t].name.clone(),
+                    weight: graph[e],
+                })
+            })
+            .collect();
+
+        Self {
+            num_nodes: graph.node_count() as u32,
+            num_edges: graph.edge_count() as u32,
+            nodes: graph.node_weights().cloned().collect(),
+            edges,
+            dependency_reports,
+            unowned_files,
+            metrics: centralities.runs,
+        }
+    }
+
+    pub fn serialize_graphviz(&self) -> String {
+        let mut graph: String = "".to_string();
+
+        graph.push_str("digraph bingraph {\n\n");
+
+        for node in self.nodes.iter() {
+            graph.push_str(&format!(
+                "  \"{}\" [style=filled, color=\"green\", tooltip=\"Version: {}\\nFiles: {}\\nOutdegree: {}\\nIndegree: {}\\nBetweeness: {}\\nKatz: {}\\nEigen: {}\\nCloseness: {}\"];\n",
+                node.name,
+                node.version,
+                node.files,
+                node.out_degree,
+                node.in_degree,
+                node.betweenness_centrality.unwrap_or_default(),
+                node.katz_centrality.unwrap_or_default(),
+                node.eigen_centrality.unwrap_or_default(),
+                node.closeness_centrality.unwrap_or_default(),
+            ));
+        }
+
+        graph.push_str("\n\n");
+
+        for edge in self.edges.iter() {
+            graph.push_str(&format!(
+                "  \"{}\" -> \"{}\" [label={}, penwidth={}]\n",
+                edge.source,
+                edge.target,
+                edge.weight,
+                1. + (edge.weight as f64).ln()
+            ));
+        }
+
+        graph.push_str("\n}");
+        graph
+    }
+}
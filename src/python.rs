@@ -0,0 +1,315 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{
+    errors::BingraphError,
+    graph::BinGraph,
+    node::{BinNode, NodeType},
+    pathiter::{normalize, PathIterator},
+};
+
+/// Directories under which versioned Python installations keep their packages.
+const PYTHON_LIB_DIRS: [&str; 3] = ["/usr/lib", "/usr/lib64", "/usr/local/lib"];
+
+/// The shared libraries that a distribution bundles next to its extension
+/// modules, rather than linking against those of the system.
+#[derive(Debug, Serialize)]
+pub struct VendoredLibraries {
+    /// The installing distribution, absent for files no RECORD lists.
+    distribution: Option<String>,
+    libraries: Vec<String>,
+}
+
+/// Returns whether the file name carries a CPython or PyPy extension suffix.
+pub fn is_extension_name(name: &str) -> bool {
+    name.ends_with(".so")
+        && (name.contains(".cpython-") || name.contains(".abi3.") || name.contains(".pypy"))
+}
+
+/// Returns whether the file name looks like a shared object, versioned or not.
+fn is_shared_object_name(name: &str) -> bool {
+    name.ends_with(".so") || name.contains(".so.")
+}
+
+/// Find the site-packages and dist-packages directories of every Python 3
+/// installation on the system.
+pub fn default_site_packages() -> Vec<String> {
+    let mut dirs: Vec<String> = vec![];
+
+    for lib in PYTHON_LIB_DIRS {
+        let Ok(entries) = fs::read_dir(lib) else {
+            continue;
+        };
+
+        let mut versions: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|n| n.starts_with("python3"))
+            .collect();
+        versions.sort();
+
+        for version in versions {
+            for packages in ["site-packages", "dist-packages"] {
+                let dir = format!("{}/{}/{}", lib, version, packages);
+                if Path::new(&dir).is_dir() {
+                    dirs.push(dir);
+                }
+            }
+        }
+    }
+
+    dirs
+}
+
+/// Read the distribution name out of a `METADATA` file, falling back to the
+/// name encoded in the `.dist-info` directory.
+fn distribution_name(dist_info: &Path) -> String {
+    let metadata = fs::read_to_string(dist_info.join("METADATA")).unwrap_or_default();
+    let name = metadata
+        .lines()
+        .take_while(|l| !l.is_empty())
+        .find_map(|l| l.strip_prefix("Name:"))
+        .map(|n| n.trim().to_string());
+
+    name.unwrap_or_else(|| {
+        let dir = dist_info
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let stem = dir.strip_suffix(".dist-info").unwrap_or(&dir);
+        stem.split('-').next().unwrap_or(stem).to_string()
+    })
+}
+
+/// Map every file recorded in a `.dist-info/RECORD` below the site directory
+/// onto the name of the distribution that installed it.
+pub fn distributions(site_dir: &str) -> HashMap<String, String> {
+    let mut owners: HashMap<String, String> = HashMap::new();
+
+    let Ok(entries) = fs::read_dir(site_dir) else {
+        return owners;
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let dist_info = entry.path();
+        if dist_info.extension().and_then(|e| e.to_str()) != Some("dist-info") {
+            continue;
+        }
+
+        let Ok(record) = fs::read_to_string(dist_info.join("RECORD")) else {
+            continue;
+        };
+
+        let name = distribution_name(&dist_info);
+        for line in record.lines() {
+            // Paths containing commas are quoted, but never contain quotes.
+            let file = match line.strip_prefix('"') {
+                Some(quoted) => quoted.split('"').next(),
+                None => line.split(',').next(),
+            };
+            let Some(file) = file.filter(|f| !f.is_empty()) else {
+                continue;
+            };
+
+            let path = normalize(&Path::new(site_dir).join(file));
+            owners.insert(path, name.clone());
+        }
+    }
+
+    owners
+}
+
+/// Walk a site directory and create a node for every shared object in it,
+/// tagging each with the distribution that installed it.
+pub fn scan(site_dir: &str) -> Vec<BinNode> {
+    println!("searching through {} for python extensions", site_dir);
+
+    let owners = distributions(site_dir);
+
+    let mut nodes: Vec<BinNode> = vec![];
+    for entry in PathIterator::recursive(site_dir) {
+        let path = entry.path();
+        if !is_shared_object_name(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+
+        match BinNode::from_entry(entry) {
+            Ok(n) => nodes.extend(n),
+            Err(e) => println!("unable to create node at {:?}: {}", path, e),
+        }
+    }
+
+    for node in nodes.iter_mut() {
+        let path = normalize(Path::new(node.absolute_path()));
+        if let Some(dist) = owners.get(&path) {
+            node.set_distribution(dist.clone());
+        }
+    }
+
+    nodes
+}
+
+/// Group the shared objects found in site directories that are not extension
+/// modules by the distribution that installed them.
+fn vendored_libraries(nodes: &[BinNode]) -> Vec<VendoredLibraries> {
+    let mut vendored: BTreeMap<Option<&str>, Vec<String>> = BTreeMap::new();
+    for node in nodes.iter() {
+        if *node.node_type() != NodeType::PythonExtension {
+            vendored
+                .entry(node.distribution())
+                .or_default()
+                .push(node.absolute_path().to_string());
+        }
+    }
+
+    vendored
+        .into_iter()
+        .map(|(dist, libraries)| VendoredLibraries {
+            distribution: dist.map(String::from),
+            libraries,
+        })
+        .collect()
+}
+
+/// Build the graph of every Python extension module and the libraries they
+/// load, reporting the shared libraries each distribution vendors.
+pub fn python_graph(
+    site_packages: Option<String>,
+    lib_path: &str,
+) -> Result<BinGraph, BingraphError> {
+    let site_dirs = match site_packages {
+        Some(s) => s.split(':').map(String::from).collect(),
+        None => default_site_packages(),
+    };
+
+    let mut nodes: Vec<BinNode> = vec![];
+    for dir in site_dirs.iter().filter(|d| !d.is_empty()) {
+        nodes.extend(scan(dir));
+    }
+
+    // Anything in site-packages that is not an extension module was bundled
+    // by the distribution, usually into a `<package>.libs` directory.
+    let vendored = vendored_libraries(&nodes);
+    for v in vendored.iter() {
+        println!(
+            "{} vendors {} libraries: {:?}",
+            v.distribution.as_deref().unwrap_or("<unowned>"),
+            v.libraries.len(),
+            v.libraries
+        );
+    }
+
+    nodes.extend(BinGraph::scan(lib_path));
+    let mut g = BinGraph::from_nodes(nodes)?;
+    g.set_vendored_libraries(vendored);
+    Ok(g)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_names() {
+        assert!(is_extension_name("_ssl.cpython-311-x86_64-linux-gnu.so"));
+        assert!(is_extension_name("_cffi_backend.abi3.so"));
+        assert!(is_extension_name("_rust.pypy39-pp73-x86_64-linux-gnu.so"));
+        assert!(!is_extension_name("libgfortran-040039e1.so.5.0.0"));
+        assert!(is_shared_object_name("libgfortran-040039e1.so.5.0.0"));
+        assert!(!is_shared_object_name("numpy/__init__.py"));
+    }
+
+    #[test]
+    fn record_files_map_onto_distributions() {
+        let site = std::env::temp_dir().join(format!("bingraph-site-{}", std::process::id()));
+        let dist_info = site.join("numpy-1.26.4.dist-info");
+        fs::create_dir_all(&dist_info).unwrap();
+        fs::write(
+            dist_info.join("METADATA"),
+            "Metadata-Version: 2.1\nName: numpy\nVersion: 1.26.4\n\nName: not-this\n",
+        )
+        .unwrap();
+        fs::write(
+            dist_info.join("RECORD"),
+            "numpy/core/_multiarray_umath.cpython-311-x86_64-linux-gnu.so,sha256=x,1\n\"numpy.libs/lib,odd.so\",,\n../../../bin/f2py,,\n",
+        )
+        .unwrap();
+        let no_metadata = site.join("six-1.16.0.dist-info");
+        fs::create_dir_all(&no_metadata).unwrap();
+        fs::write(no_metadata.join("RECORD"), "six.py,,\n").unwrap();
+
+        let site_dir = site.to_string_lossy().to_string();
+        let owners = distributions(&site_dir);
+        let owner = |p: &str| {
+            owners
+                .get(&format!("{}/{}", site_dir, p))
+                .map(String::as_str)
+        };
+
+        assert_eq!(
+            owner("numpy/core/_multiarray_umath.cpython-311-x86_64-linux-gnu.so"),
+            Some("numpy")
+        );
+        assert_eq!(owner("numpy.libs/lib,odd.so"), Some("numpy"));
+        assert_eq!(owner("six.py"), Some("six"));
+        let bin = normalize(&site.join("../../../bin/f2py"));
+        assert_eq!(owners.get(&bin).map(String::as_str), Some("numpy"));
+
+        fs::remove_dir_all(site).unwrap();
+    }
+
+    #[test]
+    fn vendored_libraries_by_distribution() {
+        let node = |path: &str, t: NodeType, dist: Option<&str>| {
+            let mut n = BinNode::new(path.rsplit('/').next().unwrap().into(), path.into(), t);
+            if let Some(d) = dist {
+                n.set_distribution(d.to_string());
+            }
+            n
+        };
+        let nodes = vec![
+            node("/s/np/_m.abi3.so", NodeType::PythonExtension, Some("numpy")),
+            node(
+                "/s/numpy.libs/libgfortran.so.5",
+                NodeType::ELFLibrary,
+                Some("numpy"),
+            ),
+            node(
+                "/s/numpy.libs/libopenblas.so",
+                NodeType::ELFLibrary,
+                Some("numpy"),
+            ),
+            node("/s/stray/libfoo.so", NodeType::ELFLibrary, None),
+        ];
+
+        let vendored = vendored_libraries(&nodes);
+        assert_eq!(vendored.len(), 2);
+        assert_eq!(vendored[0].distribution, None);
+        assert_eq!(vendored[0].libraries, vec!["/s/stray/libfoo.so"]);
+        assert_eq!(vendored[1].distribution.as_deref(), Some("numpy"));
+        assert_eq!(vendored[1].libraries.len(), 2);
+    }
+}
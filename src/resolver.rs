@@ -0,0 +1,432 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::collections::{BTreeSet, HashMap};
+
+use rustworkx_core::petgraph::{
+    graph::{DiGraph, NodeIndex},
+    visit::IntoNodeReferences,
+};
+
+use serde::Serialize;
+
+use crate::{
+    live,
+    node::{BinNode, Dependency, EdgeType},
+    pe::{self, LoaderTables},
+};
+
+/// Resolution names the rule by which a dependency was matched to a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// A Java module found by its module name.
+    ModuleName,
+
+    /// One of the candidate paths carried by the dependency, such as a
+    /// runpath entry or a class path reference.
+    SearchPath,
+
+    /// Any scanned file with the same name, as from the default search path.
+    FileName,
+
+    /// A Windows API set contract forwarded to its host.
+    ApiSet,
+
+    /// A KnownDLL, taken from the system directory.
+    KnownDll,
+
+    /// A DLL in the same directory as the importing image.
+    AppDir,
+
+    /// A DLL in a Windows system directory.
+    SystemDir,
+
+    /// A DLL in the Windows directory.
+    WindowsDir,
+
+    /// A WebAssembly module exporting every imported field.
+    WasmExports,
+}
+
+impl Resolution {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resolution::ModuleName => "module_name",
+            Resolution::SearchPath => "search_path",
+            Resolution::FileName => "file_name",
+            Resolution::ApiSet => "api_set",
+            Resolution::KnownDll => "known_dll",
+            Resolution::AppDir => "app_dir",
+            Resolution::SystemDir => "system_dir",
+            Resolution::WindowsDir => "windows_dir",
+            Resolution::WasmExports => "wasm_exports",
+        }
+    }
+}
+
+impl Serialize for Resolution {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// Returns the directory portion of a path.
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map(|(d, _)| d).unwrap_or_default()
+}
+
+/// A Resolver maps the dependencies declared by a node onto the nodes
+/// that have been scanned into the graph.
+pub struct Resolver<'a> {
+    by_name: HashMap<String, Vec<NodeIndex>>,
+    by_path: HashMap<String, Vec<NodeIndex>>,
+
+    /// Lowercased file names, for the case-insensitive Windows loader.
+    by_lower_name: HashMap<String, Vec<NodeIndex>>,
+
+    /// Shared libraries exporting each symbol, with strong definitions
+    /// ordered after weak ones so they are picked first. Only populated when
+    /// there are static objects in the graph that need linking.
+    by_symbol: HashMap<String, Vec<NodeIndex>>,
+
+    /// Every WebAssembly module, to match imports against exports.
+    wasm_modules: Vec<NodeIndex>,
+
+    /// Java archives by their module name.
+    by_module: HashMap<String, Vec<NodeIndex>>,
+
+    /// The KnownDLLs and API sets PE imports are resolved against.
+    loader: &'a LoaderTables,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(graph: &DiGraph<BinNode, EdgeType>, loader: &'a LoaderTables) -> Self {
+        let mut by_name: HashMap<String, Vec<NodeIndex>> = HashMap::new();
+        let mut by_path: HashMap<String, Vec<NodeIndex>> = HashMap::new();
+        let mut by_lower_name: HashMap<String, Vec<NodeIndex>> = HashMap::new();
+        let mut by_symbol: HashMap<String, Vec<NodeIndex>> = HashMap::new();
+        let mut wasm_modules: Vec<NodeIndex> = vec![];
+        let mut by_module: HashMap<String, Vec<NodeIndex>> = HashMap::new();
+
+        let needs_symbols = graph.node_weights().any(|n| !n.members().is_empty());
+
+        for (idx, node) in graph.node_references() {
+            let path = node.absolute_path();
+            let file_name = path.rsplit('/').next().unwrap_or(path);
+
+            by_name.entry(node.name()).or_default().push(idx);
+            if file_name != node.name() {
+                by_name.entry(file_name.to_string()).or_default().push(idx);
+            }
+            by_path.entry(path.to_string()).or_default().push(idx);
+            by_lower_name
+                .entry(file_name.to_lowercase())
+                .or_default()
+                .push(idx);
+
+            if node.node_type().is_wasm() {
+                wasm_modules.push(idx);
+            }
+
+            if let Some(info) = node.jar_info() {
+                by_module
+                    .entry(info.module_name().to_string())
+                    .or_default()
+                    .push(idx);
+            }
+
+            if needs_symbols {
+                for sym in node.exports() {
+                    let providers = by_symbol.entry(sym.name.clone()).or_default();
+                    if sym.weak {
+                        providers.insert(0, idx);
+                    } else {
+                        providers.push(idx);
+                    }
+                }
+            }
+        }
+
+        // Runtime paths are canonical, so symlinked scan paths are also
+        // indexed under their target unless a node already sits there.
+        let mut aliases: HashMap<String, Vec<NodeIndex>> = HashMap::new();
+        for (idx, node) in graph.node_references() {
+            let target = live::canonical(node.absolute_path());
+            if !by_path.contains_key(&target) {
+                aliases.entry(target).or_default().push(idx);
+            }
+        }
+        by_path.extend(aliases);
+
+        Self {
+            by_name,
+            by_path,
+            by_lower_name,
+            by_symbol,
+            wasm_modules,
+            by_module,
+            loader,
+        }
+    }
+
+    /// Compute the link-time dependencies of a static archive or object: every
+    /// shared library that would satisfy one of its undefined symbols.
+    pub fn link_dependencies(
+        &self,
+        graph: &DiGraph<BinNode, EdgeType>,
+        node: &BinNode,
+    ) -> Vec<Dependency> {
+        let providers: BTreeSet<NodeIndex> = node
+            .members()
+            .iter()
+            .flat_map(|m| m.undefined_symbols())
+            .filter_map(|sym| Self::pick(graph, node, self.by_symbol.get(sym), |_| true))
+            .collect();
+
+        providers
+            .into_iter()
+            .map(|idx| {
+                let lib = &graph[idx];
+                Dependency::new(&lib.name(), EdgeType::LinkTime)
+                    .with_paths(vec![lib.absolute_path().to_string()])
+            })
+            .collect()
+    }
+
+    /// Find the node that a dependency of `node` refers to. Explicit candidate
+    /// paths are tried first, in order, before falling back to the file name.
+    pub fn resolve(
+        &self,
+        graph: &DiGraph<BinNode, EdgeType>,
+        node: &BinNode,
+        dep: &Dependency,
+    ) -> Option<NodeIndex> {
+        self.resolve_with_rule(graph, node, dep).map(|(idx, _)| idx)
+    }
+
+    /// Resolve a dependency like `resolve`, also returning the rule that
+    /// matched it.
+    pub fn resolve_with_rule(
+        &self,
+        graph: &DiGraph<BinNode, EdgeType>,
+        node: &BinNode,
+        dep: &Dependency,
+    ) -> Option<(NodeIndex, Resolution)> {
+        match node.node_type() {
+            t if t.is_portable() => return self.resolve_windows(graph, node, dep),
+            t if t.is_wasm() => return self.resolve_wasm(graph, node, dep),
+            _ => {}
+        }
+
+        if dep.kind == EdgeType::Requires && node.jar_info().is_some() {
+            return Self::pick(graph, node, self.by_module.get(&dep.name), |_| true)
+                .map(|idx| (idx, Resolution::ModuleName));
+        }
+
+        dep.paths
+            .iter()
+            .find_map(|p| Self::pick(graph, node, self.by_path.get(p), |_| true))
+            .map(|idx| (idx, Resolution::SearchPath))
+            .or_else(|| {
+                Self::pick(graph, node, self.by_name.get(dep.file_name()), |_| true)
+                    .map(|idx| (idx, Resolution::FileName))
+            })
+    }
+
+    /// Resolve a WebAssembly import module name. A scanned module named after
+    /// the import wins, otherwise any other module exporting every imported
+    /// field satisfies it.
+    fn resolve_wasm(
+        &self,
+        graph: &DiGraph<BinNode, EdgeType>,
+        node: &BinNode,
+        dep: &Dependency,
+    ) -> Option<(NodeIndex, Resolution)> {
+        let is_other_module =
+            |n: &BinNode| n.node_type().is_wasm() && n.absolute_path() != node.absolute_path();
+
+        let exports_all = |n: &BinNode| {
+            dep.symbols
+                .iter()
+                .all(|s| n.exports().iter().any(|e| e.name == *s))
+        };
+
+        Self::pick(graph, node, self.by_name.get(&dep.name), is_other_module)
+            .or_else(|| {
+                let file_name = format!("{}.wasm", dep.name);
+                Self::pick(graph, node, self.by_name.get(&file_name), is_other_module)
+            })
+            .map(|idx| (idx, Resolution::FileName))
+            .or_else(|| {
+                self.wasm_modules
+                    .iter()
+                    .copied()
+                    .find(|idx| is_other_module(&graph[*idx]) && exports_all(&graph[*idx]))
+                    .map(|idx| (idx, Resolution::WasmExports))
+            })
+    }
+
+    /// Resolve a PE import following the Windows loader: API set contracts are
+    /// forwarded to their host, KnownDLLs always come from the system directory,
+    /// and otherwise the application directory is searched before the system
+    /// and Windows directories and finally anywhere else that was scanned.
+    fn resolve_windows(
+        &self,
+        graph: &DiGraph<BinNode, EdgeType>,
+        node: &BinNode,
+        dep: &Dependency,
+    ) -> Option<(NodeIndex, Resolution)> {
+        let name = dep.file_name().to_lowercase();
+        let in_system_dir = |n: &BinNode| pe::is_system_path(&n.absolute_path().to_lowercase());
+
+        if let Some(host) = self.loader.api_set_host(&name) {
+            let hosted = Self::pick(graph, node, self.by_lower_name.get(host), in_system_dir);
+            if let Some(idx) = hosted {
+                return Some((idx, Resolution::ApiSet));
+            }
+        }
+
+        let candidates = self.by_lower_name.get(&name);
+
+        if self.loader.is_known_dll(&name) {
+            return Self::pick(graph, node, candidates, in_system_dir)
+                .map(|idx| (idx, Resolution::KnownDll));
+        }
+
+        let app_dir = parent_dir(node.absolute_path()).to_lowercase();
+        Self::pick(graph, node, candidates, |n| {
+            parent_dir(n.absolute_path()).to_lowercase() == app_dir
+        })
+        .map(|idx| (idx, Resolution::AppDir))
+        .or_else(|| {
+            Self::pick(graph, node, candidates, in_system_dir)
+                .map(|idx| (idx, Resolution::SystemDir))
+        })
+        .or_else(|| {
+            Self::pick(graph, node, candidates, |n| {
+                parent_dir(n.absolute_path())
+                    .to_lowercase()
+                    .ends_with("/windows")
+            })
+            .map(|idx| (idx, Resolution::WindowsDir))
+        })
+        .or_else(|| {
+            Self::pick(graph, node, candidates, |_| true).map(|idx| (idx, Resolution::FileName))
+        })
+    }
+
+    /// Pick the candidate that the node could actually load. Later entries win,
+    /// as directories earlier in the search path are scanned last.
+    fn pick(
+        graph: &DiGraph<BinNode, EdgeType>,
+        node: &BinNode,
+        candidates: Option<&Vec<NodeIndex>>,
+        filter: impl Fn(&BinNode) -> bool,
+    ) -> Option<NodeIndex> {
+        candidates?.iter().rev().copied().find(|idx| {
+            let candidate = &graph[*idx];
+            let compatible = match (node.arch(), candidate.arch()) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            };
+            compatible && filter(candidate)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::NodeType;
+
+    #[test]
+    fn resolves_symlinked_paths_by_target() {
+        let dir = std::env::temp_dir().join(format!("bingraph-resolver-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("libfoo.so.1.2");
+        let link = dir.join("libfoo.so.1");
+        std::fs::write(&target, b"").unwrap();
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let mut graph: DiGraph<BinNode, EdgeType> = DiGraph::new();
+        let lib = graph.add_node(BinNode::new(
+            "libfoo.so.1".to_string(),
+            link.to_string_lossy().to_string(),
+            NodeType::ELFLibrary,
+        ));
+        let proc = BinNode::new(
+            "foo[1]".to_string(),
+            "/bin/foo".to_string(),
+            NodeType::Process,
+        );
+
+        let canonical = live::canonical(&target.to_string_lossy());
+        let dep = Dependency::new(&canonical, EdgeType::Mapped).with_paths(vec![canonical.clone()]);
+
+        let loader = LoaderTables::default();
+        let resolver = Resolver::new(&graph, &loader);
+        assert_eq!(
+            resolver.resolve_with_rule(&graph, &proc, &dep),
+            Some((lib, Resolution::SearchPath))
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resolves_windows_imports_with_loader_tables() {
+        let mut graph: DiGraph<BinNode, EdgeType> = DiGraph::new();
+        let mut add = |path: &str, node_type: NodeType| {
+            let name = path.rsplit('/').next().unwrap().to_string();
+            graph.add_node(BinNode::new(name, path.to_string(), node_type))
+        };
+        let system = add("/win/Windows/System32/KERNEL32.dll", NodeType::PortableDll);
+        let base = add(
+            "/win/Windows/System32/kernelbase.dll",
+            NodeType::PortableDll,
+        );
+        // A copy in the application directory does not shadow a KnownDLL.
+        add("/win/app/kernel32.dll", NodeType::PortableDll);
+        let zlib = add("/win/app/zlib1.dll", NodeType::PortableDll);
+        let exe = BinNode::new(
+            "app.exe".to_string(),
+            "/win/app/app.exe".to_string(),
+            NodeType::PortableExecutable,
+        );
+
+        let loader = LoaderTables::default();
+        let resolver = Resolver::new(&graph, &loader);
+        let resolve = |name: &str| {
+            resolver.resolve_with_rule(&graph, &exe, &Dependency::new(name, EdgeType::Needed))
+        };
+
+        assert_eq!(
+            resolve("kernel32.dll"),
+            Some((system, Resolution::KnownDll))
+        );
+        assert_eq!(
+            resolve("api-ms-win-core-synch-l1-2-0.dll"),
+            Some((base, Resolution::ApiSet))
+        );
+        assert_eq!(resolve("ZLIB1.DLL"), Some((zlib, Resolution::AppDir)));
+    }
+}
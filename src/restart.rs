@@ -0,0 +1,331 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{collections::BTreeMap, fs, os::unix::fs::MetadataExt};
+
+use serde::Serialize;
+
+use crate::live::{self, MappedFile, Process};
+
+/// FileState is whether a file a process runs is still the one on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileState {
+    Current,
+
+    /// The file was deleted or another file took its place.
+    Replaced,
+
+    /// The file on disk could not be inspected.
+    Unknown,
+}
+
+impl Serialize for FileState {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            FileState::Current => serializer.serialize_str("current"),
+            FileState::Replaced => serializer.serialize_str("replaced"),
+            FileState::Unknown => serializer.serialize_str("unknown"),
+        }
+    }
+}
+
+/// A StaleProcess is a process still running code that has since been
+/// replaced or deleted on disk, or whose files could not be inspected.
+#[derive(Debug, Serialize)]
+pub struct StaleProcess {
+    pid: u32,
+    exe: String,
+    exe_state: FileState,
+    stale_libraries: Vec<String>,
+
+    /// Mapped libraries whose file on disk could not be inspected.
+    unknown_libraries: Vec<String>,
+}
+
+/// A process whose files could not all be inspected, along with the unit it
+/// runs as part of, if any.
+#[derive(Debug, Serialize)]
+pub struct UninspectableProcess {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<String>,
+
+    #[serde(flatten)]
+    process: StaleProcess,
+}
+
+/// The stale processes running as part of a single systemd unit.
+#[derive(Debug, Serialize)]
+pub struct UnitRestart {
+    unit: String,
+    processes: Vec<StaleProcess>,
+}
+
+/// A RestartReport lists the units and processes that need restarting to
+/// pick up upgraded files.
+#[derive(Debug, Serialize)]
+pub struct RestartReport {
+    units: Vec<UnitRestart>,
+
+    /// Stale processes that do not belong to any systemd unit.
+    unmanaged: Vec<StaleProcess>,
+
+    /// Processes with files that could not be inspected, but none known to
+    /// be replaced. Their units are not reported as needing a restart.
+    uninspectable: Vec<UninspectableProcess>,
+
+    num_processes: u32,
+    num_stale: u32,
+    num_unknown: u32,
+}
+
+/// Compare a file as a process mapped it against the file at the same path
+/// on disk, as seen from the root directory of that process.
+fn file_state(proc_dir: &str, pid: u32, path: &str, dev: u64, inode: u64) -> FileState {
+    match fs::metadata(format!("{}/{}/root{}", proc_dir, pid, path)) {
+        Ok(md) if md.dev() == dev && md.ino() == inode => FileState::Current,
+        Ok(_) => FileState::Replaced,
+        Err(_) => FileState::Unknown,
+    }
+}
+
+/// Returns whether a mapped file is still the one on disk.
+fn mapped_state(proc_dir: &str, pid: u32, m: &MappedFile) -> FileState {
+    if m.deleted {
+        return FileState::Replaced;
+    }
+    file_state(proc_dir, pid, &m.path, m.dev, m.inode)
+}
+
+/// Returns whether the executable of a process is still the one on disk.
+fn exe_state(proc_dir: &str, p: &Process) -> FileState {
+    if p.exe_deleted {
+        return FileState::Replaced;
+    }
+
+    // The exe link of a process stats the file it was executed from.
+    match fs::metadata(format!("{}/{}/exe", proc_dir, p.pid)) {
+        Ok(md) => file_state(proc_dir, p.pid, &p.exe, md.dev(), md.ino()),
+        Err(_) => FileState::Unknown,
+    }
+}
+
+/// Find the systemd unit owning a process from its `/proc/<pid>/cgroup`,
+/// the innermost service or scope along the cgroup path. Both the unified
+/// hierarchy and the legacy `name=systemd` hierarchy are understood.
+fn unit_of(cgroup: &str) -> Option<String> {
+    let path = cgroup.lines().find_map(|l| {
+        let (_, rest) = l.split_once(':')?;
+        let (controllers, path) = rest.split_once(':')?;
+        matches!(controllers, "" | "name=systemd").then_some(path)
+    })?;
+
+    path.rsplit('/')
+        .find(|c| c.ends_with(".service") || c.ends_with(".scope"))
+        .map(|c| c.to_string())
+}
+
+/// Inspect every visible process for replaced or deleted files, grouping
+/// the ones that need a restart by the systemd unit they belong to.
+pub fn report(proc_dir: &str) -> RestartReport {
+    let procs = live::processes(proc_dir);
+
+    let mut units: BTreeMap<String, Vec<StaleProcess>> = BTreeMap::new();
+    let mut unmanaged: Vec<StaleProcess> = vec![];
+    let mut uninspectable: Vec<UninspectableProcess> = vec![];
+    let mut num_stale = 0;
+
+    for p in procs.iter() {
+        let exe_state = exe_state(proc_dir, p);
+        let mut stale_libraries: Vec<String> = vec![];
+        let mut unknown_libraries: Vec<String> = vec![];
+        for m in p.mapped.iter() {
+            match mapped_state(proc_dir, p.pid, m) {
+                FileState::Current => {}
+                FileState::Replaced => stale_libraries.push(m.path.clone()),
+                FileState::Unknown => unknown_libraries.push(m.path.clone()),
+            }
+        }
+
+        let replaced = exe_state == FileState::Replaced || !stale_libraries.is_empty();
+        let unknown = exe_state == FileState::Unknown || !unknown_libraries.is_empty();
+        if !replaced && !unknown {
+            continue;
+        }
+
+        let stale = StaleProcess {
+            pid: p.pid,
+            exe: p.exe.clone(),
+            exe_state,
+            stale_libraries,
+            unknown_libraries,
+        };
+
+        let cgroup =
+            fs::read_to_string(format!("{}/{}/cgroup", proc_dir, p.pid)).unwrap_or_default();
+        let unit = unit_of(&cgroup);
+
+        // Only processes known to run replaced files call for a restart.
+        if !replaced {
+            uninspectable.push(UninspectableProcess {
+                unit,
+                process: stale,
+            });
+            continue;
+        }
+        num_stale += 1;
+        match unit {
+            Some(unit) => units.entry(unit).or_default().push(stale),
+            None => unmanaged.push(stale),
+        }
+    }
+
+    for (unit, stale) in units.iter() {
+        let pids: Vec<u32> = stale.iter().map(|s| s.pid).collect();
+        println!("{} needs a restart, stale processes: {:?}", unit, pids);
+    }
+    println!(
+        "{} of {} processes are running replaced files, {} could not be inspected, {} outside of any unit",
+        num_stale,
+        procs.len(),
+        uninspectable.len(),
+        unmanaged.len()
+    );
+
+    RestartReport {
+        units: units
+            .into_iter()
+            .map(|(unit, processes)| UnitRestart { unit, processes })
+            .collect(),
+        unmanaged,
+        num_processes: procs.len() as u32,
+        num_stale,
+        num_unknown: uninspectable.len() as u32,
+        uninspectable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_innermost_unit() {
+        let unified = "0::/system.slice/foo.service/bar.scope\n";
+        assert_eq!(unit_of(unified).as_deref(), Some("bar.scope"));
+
+        let session = "0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(unit_of(session).as_deref(), Some("session-2.scope"));
+
+        let legacy = "12:cpu,cpuacct:/\n1:name=systemd:/system.slice/sshd.service\n";
+        assert_eq!(unit_of(legacy).as_deref(), Some("sshd.service"));
+
+        assert_eq!(unit_of("0::/\n"), None);
+    }
+
+    #[test]
+    fn compares_files_through_process_root() {
+        let proc_dir =
+            std::env::temp_dir().join(format!("bingraph-restart-{}", std::process::id()));
+        let lib_dir = proc_dir.join("1/root/usr/lib");
+        fs::create_dir_all(&lib_dir).unwrap();
+        fs::write(lib_dir.join("libfoo.so"), b"").unwrap();
+        let md = fs::metadata(lib_dir.join("libfoo.so")).unwrap();
+        let proc_dir = proc_dir.to_string_lossy().to_string();
+
+        let mapped = |path: &str, dev: u64, inode: u64, deleted: bool| MappedFile {
+            path: path.to_string(),
+            dev,
+            inode,
+            deleted,
+        };
+
+        let current = mapped("/usr/lib/libfoo.so", md.dev(), md.ino(), false);
+        assert_eq!(mapped_state(&proc_dir, 1, &current), FileState::Current);
+
+        let other_inode = mapped("/usr/lib/libfoo.so", md.dev(), md.ino() + 1, false);
+        assert_eq!(
+            mapped_state(&proc_dir, 1, &other_inode),
+            FileState::Replaced
+        );
+
+        let other_dev = mapped("/usr/lib/libfoo.so", md.dev() + 1, md.ino(), false);
+        assert_eq!(mapped_state(&proc_dir, 1, &other_dev), FileState::Replaced);
+
+        let deleted = mapped("/usr/lib/libfoo.so", md.dev(), md.ino(), true);
+        assert_eq!(mapped_state(&proc_dir, 1, &deleted), FileState::Replaced);
+
+        let missing = mapped("/usr/lib/libbar.so", md.dev(), md.ino(), false);
+        assert_eq!(mapped_state(&proc_dir, 1, &missing), FileState::Unknown);
+
+        fs::remove_dir_all(&proc_dir).unwrap();
+    }
+
+    /// Create a process under the proc directory, whose root is the host
+    /// root and whose exe is a file of its own, mapping the given files.
+    fn add_process(proc_dir: &std::path::Path, pid: u32, maps: &str, cgroup: &str) {
+        let dir = proc_dir.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let exe = proc_dir.join(format!("exe-{}", pid));
+        fs::write(&exe, b"").unwrap();
+        std::os::unix::fs::symlink(&exe, dir.join("exe")).unwrap();
+        std::os::unix::fs::symlink("/", dir.join("root")).unwrap();
+        fs::write(dir.join("maps"), maps).unwrap();
+        fs::write(dir.join("cgroup"), cgroup).unwrap();
+    }
+
+    #[test]
+    fn does_not_restart_units_with_only_unknown_files() {
+        let proc_dir =
+            std::env::temp_dir().join(format!("bingraph-restart-units-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&proc_dir);
+        fs::create_dir_all(&proc_dir).unwrap();
+
+        add_process(
+            &proc_dir,
+            10,
+            "7f0000000000-7f0000001000 r-xp 00000000 fd:01 42   /nonexistent/libgone.so\n",
+            "0::/system.slice/unknown.service\n",
+        );
+        add_process(
+            &proc_dir,
+            20,
+            "7f0000000000-7f0000001000 r-xp 00000000 fd:01 42   /usr/lib/libold.so (deleted)\n",
+            "0::/system.slice/stale.service\n",
+        );
+
+        let report = report(&proc_dir.to_string_lossy());
+        let units: Vec<&str> = report.units.iter().map(|u| u.unit.as_str()).collect();
+        assert_eq!(units, ["stale.service"]);
+        assert_eq!(report.num_stale, 1);
+
+        assert_eq!(report.uninspectable.len(), 1);
+        let unknown = &report.uninspectable[0];
+        assert_eq!(unknown.unit.as_deref(), Some("unknown.service"));
+        assert_eq!(unknown.process.pid, 10);
+        assert_eq!(unknown.process.exe_state, FileState::Current);
+        assert_eq!(
+            unknown.process.unknown_libraries,
+            ["/nonexistent/libgone.so"]
+        );
+
+        fs::remove_dir_all(&proc_dir).unwrap();
+    }
+}
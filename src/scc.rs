@@ -0,0 +1,241 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque};
+
+use rustworkx_core::petgraph::{
+    algo::tarjan_scc,
+    graph::{DiGraph, NodeIndex},
+};
+use serde::Serialize;
+
+use crate::graph::BinGraph;
+
+/// Components holds the strongly connected components of a graph.
+pub struct Components {
+    /// The component of every node, by node index.
+    pub component: Vec<usize>,
+
+    /// The members of every component, in reverse topological order.
+    pub members: Vec<Vec<NodeIndex>>,
+}
+
+impl Components {
+    pub fn compute<N, E>(graph: &DiGraph<N, E>) -> Self {
+        let members = tarjan_scc(graph);
+        let mut component = vec![0; graph.node_count()];
+        for (c, nodes) in members.iter().enumerate() {
+            for idx in nodes {
+                component[idx.index()] = c;
+            }
+        }
+
+        Self { component, members }
+    }
+
+    /// Returns whether a component is a cycle: more than one node, or a
+    /// single node depending upon itself.
+    pub fn is_cycle<N, E>(&self, graph: &DiGraph<N, E>, c: usize) -> bool {
+        match self.members[c].as_slice() {
+            [idx] => graph.contains_edge(*idx, *idx),
+            _ => true,
+        }
+    }
+
+    /// Find one of the shortest cycles through the first member of a cyclic
+    /// component, staying within the component. The path starts and ends
+    /// with that member.
+    pub fn cycle<N, E>(&self, graph: &DiGraph<N, E>, c: usize) -> Option<Vec<NodeIndex>> {
+        let start = *self.members[c].first()?;
+        let mut parent: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut queue: VecDeque<NodeIndex> = VecDeque::from([start]);
+
+        while let Some(idx) = queue.pop_front() {
+            for succ in graph.neighbors(idx) {
+                if self.component[succ.index()] != c {
+                    continue;
+                }
+                // Walk the parents back from the node closing the cycle.
+                if succ == start {
+                    let mut path = vec![start, idx];
+                    while let Some(p) = parent.get(path.last()?) {
+                        path.push(*p);
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                if let Entry::Vacant(e) = parent.entry(succ) {
+                    e.insert(idx);
+                    queue.push_back(succ);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// A node of the condensation, standing for one strongly connected component.
+#[derive(Debug, Serialize)]
+pub struct CondensedNode {
+    id: u32,
+    members: Vec<String>,
+    cycle: bool,
+}
+
+/// A link between two components, weighted by the edges it collapses.
+#[derive(Debug, Serialize)]
+pub struct CondensedEdge {
+    source: u32,
+    target: u32,
+    weight: u32,
+}
+
+/// The condensation of a graph is the acyclic graph left after collapsing
+/// each strongly connected component into a single node.
+#[derive(Debug, Serialize)]
+pub struct Condensation {
+    nodes: Vec<CondensedNode>,
+    edges: Vec<CondensedEdge>,
+}
+
+impl Condensation {
+    pub fn new(g: &BinGraph) -> Self {
+        let mut members: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+        for node in g.nodes() {
+            members
+                .entry(node.scc_id())
+                .or_default()
+                .push(node.absolute_path().to_string());
+        }
+
+        let mut self_loops: Vec<u32> = vec![];
+        let mut weights: BTreeMap<(u32, u32), u32> = BTreeMap::new();
+        for e in g.edges() {
+            let source = g.nodes()[e.source_idx()].scc_id();
+            let target = g.nodes()[e.target_idx()].scc_id();
+            if source == target {
+                self_loops.push(source);
+            } else {
+                *weights.entry((source, target)).or_default() += 1;
+            }
+        }
+
+        let nodes = members
+            .into_iter()
+            .map(|(id, members)| CondensedNode {
+                id,
+                cycle: members.len() > 1 || self_loops.contains(&id),
+                members,
+            })
+            .collect();
+
+        let edges = weights
+            .into_iter()
+            .map(|((source, target), weight)| CondensedEdge {
+                source,
+                target,
+                weight,
+            })
+            .collect();
+
+        Self { nodes, edges }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{BinNode, Dependency, EdgeType, NodeType};
+
+    #[test]
+    fn finds_cycles_and_self_loops() {
+        // 0 -> 1 <-> 2 -> 3, 3 -> 3, 4 alone
+        let mut graph: DiGraph<(), ()> =
+            DiGraph::from_edges([(0, 1), (1, 2), (2, 1), (2, 3), (3, 3)]);
+        graph.add_node(());
+        let components = Components::compute(&graph);
+
+        assert_eq!(components.component[1], components.component[2]);
+        assert_eq!(components.members.len(), 4);
+
+        let cycles: Vec<usize> = (0..components.members.len())
+            .filter(|c| components.is_cycle(&graph, *c))
+            .collect();
+        assert_eq!(cycles.len(), 2);
+        assert!(cycles.contains(&components.component[1]));
+        assert!(cycles.contains(&components.component[3]));
+
+        // Successors come before the components depending on them.
+        assert!(components.component[3] < components.component[1]);
+        assert!(components.component[1] < components.component[0]);
+    }
+
+    #[test]
+    fn walks_one_cycle_per_component() {
+        // 0 -> 1 -> 2 -> 0 with the shortcut 1 -> 0, 2 -> 3, 3 -> 3
+        let graph: DiGraph<(), ()> =
+            DiGraph::from_edges([(0, 1), (1, 2), (2, 0), (1, 0), (2, 3), (3, 3)]);
+        let components = Components::compute(&graph);
+
+        let cycle = components.cycle(&graph, components.component[0]).unwrap();
+        assert_eq!(cycle.first(), cycle.last());
+        assert!(cycle.windows(2).all(|e| graph.contains_edge(e[0], e[1])));
+
+        // Only the walk from 2 cannot take the shortcut between 0 and 1.
+        let shortest = if cycle[0].index() == 2 { 4 } else { 3 };
+        assert_eq!(cycle.len(), shortest);
+
+        let self_loop = NodeIndex::new(3);
+        assert_eq!(
+            components.cycle(&graph, components.component[3]),
+            Some(vec![self_loop, self_loop])
+        );
+
+        let acyclic: DiGraph<(), ()> = DiGraph::from_edges([(0, 1)]);
+        let components = Components::compute(&acyclic);
+        assert_eq!(components.cycle(&acyclic, components.component[0]), None);
+    }
+
+    #[test]
+    fn condenses_cycles_into_weighted_edges() {
+        let nodes = [("exe", &["a", "b"][..]), ("a", &["b"]), ("b", &["a"])]
+            .iter()
+            .map(|(name, deps)| {
+                let mut node =
+                    BinNode::new(name.to_string(), format!("/{}", name), NodeType::ELFLibrary);
+                node.set_dependencies(
+                    deps.iter()
+                        .map(|d| Dependency::new(d, EdgeType::Needed))
+                        .collect(),
+                );
+                node
+            })
+            .collect();
+        let g = BinGraph::from_nodes(nodes).unwrap();
+        let condensation = Condensation::new(&g);
+
+        assert_eq!(condensation.nodes.len(), 2);
+        let cycle = condensation.nodes.iter().find(|n| n.cycle).unwrap();
+        assert_eq!(cycle.members.len(), 2);
+
+        assert_eq!(condensation.edges.len(), 1);
+        assert_eq!(condensation.edges[0].target, cycle.id);
+        assert_eq!(condensation.edges[0].weight, 2);
+    }
+}
@@ -0,0 +1,468 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    node::{BinNode, Dependency, EdgeType, NodeType},
+    pathiter::normalize,
+};
+
+/// System unit directories, from highest to lowest precedence.
+const UNIT_DIRS: [&str; 5] = [
+    "/etc/systemd/system",
+    "/run/systemd/system",
+    "/usr/local/lib/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system",
+];
+
+/// Symlinks followed when resolving a unit link, before giving up on it.
+const MAX_LINKS: usize = 8;
+
+/// Directories searched for commands given without an absolute path.
+const EXEC_SEARCH_PATH: [&str; 4] = ["/usr/local/sbin", "/usr/local/bin", "/usr/sbin", "/usr/bin"];
+
+/// Keys of the `[Service]` section whose commands the service executes.
+const EXEC_KEYS: [&str; 6] = [
+    "ExecStart",
+    "ExecStartPre",
+    "ExecStartPost",
+    "ExecReload",
+    "ExecStop",
+    "ExecStopPost",
+];
+
+/// Read the `key=value` assignments of a unit file as `(section, key, value)`,
+/// joining lines continued with a trailing backslash.
+fn assignments(contents: &str) -> Vec<(String, String, String)> {
+    let mut out: Vec<(String, String, String)> = vec![];
+    let mut section = String::new();
+    let mut pending = String::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if pending.is_empty() && (line.starts_with('#') || line.starts_with(';')) {
+            continue;
+        }
+
+        if let Some(cont) = line.strip_suffix('\\') {
+            pending.push_str(cont);
+            pending.push(' ');
+            continue;
+        }
+        pending.push_str(line);
+        let line = std::mem::take(&mut pending);
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.to_string();
+        } else if let Some((k, v)) = line.split_once('=') {
+            out.push((section.clone(), k.trim().to_string(), v.trim().to_string()));
+        }
+    }
+
+    out
+}
+
+/// Extract the executable from an `Exec` command line, skipping the special
+/// prefixes systemd accepts before it.
+fn exec_path(command: &str) -> Option<String> {
+    let command = command.trim_start_matches(['-', '@', ':', '+', '!']);
+    let first = command.split_whitespace().next()?;
+    let first = first.trim_matches(|c| c == '"' || c == '\'');
+
+    (!first.is_empty() && !first.contains('$')).then(|| first.to_string())
+}
+
+/// Map an instance of a template unit, `foo@bar.service`, onto the template
+/// `foo@.service` that is installed on disk.
+fn template_name(unit: &str) -> String {
+    match (unit.split_once('@'), unit.rsplit_once('.')) {
+        (Some((prefix, _)), Some((_, suffix))) => format!("{}@.{}", prefix, suffix),
+        _ => unit.to_string(),
+    }
+}
+
+/// The paths a command may be found at below the scan root. Merged-usr
+/// systems refer to binaries both with and without the `/usr` prefix.
+fn exec_candidates(root: &str, command: &str) -> Vec<String> {
+    let paths: Vec<String> = if command.starts_with('/') {
+        let mut paths = vec![command.to_string()];
+        match command.strip_prefix("/usr") {
+            Some(p) => paths.push(p.to_string()),
+            None => paths.push(format!("/usr{}", command)),
+        }
+        paths
+    } else {
+        EXEC_SEARCH_PATH
+            .iter()
+            .map(|d| format!("{}/{}", d, command))
+            .collect()
+    };
+
+    paths
+        .into_iter()
+        .map(|p| normalize(Path::new(&format!("{}{}", root, p))))
+        .collect()
+}
+
+/// Follow a unit file symlink within the scan root. Absolute targets are
+/// taken relative to the root rather than the host, and relative ones can
+/// not climb out of it. A link to `/dev/null` is returned as is.
+fn resolve_link(root: &str, path: &Path) -> Option<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_LINKS {
+        if !path.is_symlink() {
+            return Some(path);
+        }
+
+        let target = fs::read_link(&path).ok()?;
+        if target == Path::new("/dev/null") {
+            return Some(target);
+        }
+
+        let parent = path.parent()?.strip_prefix(root).ok()?;
+        let target = normalize(&Path::new("/").join(parent).join(target));
+        path = PathBuf::from(format!("{}{}", root, target));
+    }
+
+    None
+}
+
+/// Units found below the root: the files of every service, keeping only the
+/// highest precedence file for each name along with its drop-ins, and the
+/// services that alias names resolve to.
+struct ServiceFiles {
+    units: BTreeMap<String, Vec<PathBuf>>,
+    aliases: BTreeMap<String, String>,
+}
+
+/// Find every service unit below the root.
+fn service_files(root: &str) -> ServiceFiles {
+    let mut units: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    let mut aliases: BTreeMap<String, String> = BTreeMap::new();
+
+    for dir in UNIT_DIRS {
+        let Ok(entries) = fs::read_dir(format!("{}{}", root, dir)) else {
+            continue;
+        };
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".service")
+                || units.contains_key(&name)
+                || aliases.contains_key(&name)
+            {
+                continue;
+            }
+
+            // Masked units link to /dev/null and hide any lower precedence
+            // file, while aliases link to a unit under another name.
+            let mut path = entry.path();
+            if path.is_symlink() {
+                let Some(target) = resolve_link(root, &path) else {
+                    println!("unable to resolve unit link {:?}", path);
+                    continue;
+                };
+                if target == Path::new("/dev/null") {
+                    units.insert(name, vec![]);
+                    continue;
+                }
+
+                let target_name = target
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                if target_name != name {
+                    aliases.insert(name, target_name);
+                    continue;
+                }
+                path = target;
+            }
+
+            units.insert(name, vec![path]);
+        }
+    }
+    units.retain(|_, files| !files.is_empty());
+
+    // Drop-ins are applied from every directory, in name order.
+    for (name, files) in units.iter_mut() {
+        let mut dropins: Vec<PathBuf> = UNIT_DIRS
+            .iter()
+            .filter_map(|d| fs::read_dir(format!("{}{}/{}.d", root, d, name)).ok())
+            .flatten()
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("conf"))
+            .collect();
+        dropins.sort_by_key(|p| p.file_name().map(|n| n.to_os_string()));
+        files.extend(dropins);
+    }
+
+    ServiceFiles { units, aliases }
+}
+
+/// Create a node for a service from its unit file and drop-ins. An empty
+/// `Exec` assignment resets the commands given before it, as in systemd.
+/// Units required under an alias depend on the unit it names.
+fn service_node(
+    root: &str,
+    name: String,
+    files: &[PathBuf],
+    aliases: &BTreeMap<String, String>,
+) -> BinNode {
+    let mut exec: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut deps: Vec<Dependency> = vec![];
+
+    for file in files {
+        let Ok(contents) = fs::read_to_string(file) else {
+            println!("unable to read unit file {:?}", file);
+            continue;
+        };
+
+        for (section, key, value) in assignments(&contents) {
+            match (section.as_str(), key.as_str()) {
+                ("Service", k) if EXEC_KEYS.contains(&k) => {
+                    let commands = exec.entry(key).or_default();
+                    if value.is_empty() {
+                        commands.clear();
+                    } else {
+                        commands.extend(exec_path(&value));
+                    }
+                }
+                ("Unit", "Requires") | ("Unit", "Wants") => {
+                    let kind = if key == "Requires" {
+                        EdgeType::Requires
+                    } else {
+                        EdgeType::Wants
+                    };
+                    deps.extend(value.split_whitespace().map(|u| {
+                        let unit = aliases.get(u).map(String::as_str).unwrap_or(u);
+                        Dependency::new(&template_name(unit), kind)
+                    }));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let commands: BTreeSet<String> = exec.into_values().flatten().collect();
+    for command in commands {
+        let paths = exec_candidates(root, &command);
+        deps.push(Dependency::new(&command, EdgeType::Exec).with_paths(paths));
+    }
+
+    let path = files
+        .first()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut node = BinNode::new(name, path, NodeType::Service);
+    node.set_dependencies(deps);
+    node
+}
+
+/// Create a node for every systemd service installed below the scan root.
+pub fn scan(root: &str) -> Vec<BinNode> {
+    let root = root.trim_end_matches('/');
+    println!("searching through {}/ for systemd services", root);
+
+    let files = service_files(root);
+    files
+        .units
+        .into_iter()
+        .map(|(name, unit_files)| service_node(root, name, &unit_files, &files.aliases))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// Create an empty scan root unique to the test.
+    fn root(name: &str) -> String {
+        let root = std::env::temp_dir().join(format!("bingraph-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root.to_string_lossy().to_string()
+    }
+
+    fn write(root: &str, path: &str, contents: &str) {
+        let path = format!("{}{}", root, path);
+        fs::create_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn link(root: &str, path: &str, target: &str) {
+        let path = format!("{}{}", root, path);
+        fs::create_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+        symlink(target, path).unwrap();
+    }
+
+    fn deps(node: &BinNode, kind: EdgeType) -> Vec<String> {
+        node.get_dependencies()
+            .iter()
+            .filter(|d| d.kind == kind)
+            .map(|d| d.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn parses_unit_assignments() {
+        let unit = "\
+# comment
+[Unit]
+Requires=a.service \\
+  b.service
+; another comment
+[Service]
+ExecStart=-/usr/bin/foo --flag
+ExecStartPre=@/usr/bin/bar bar
+";
+        let a = assignments(unit);
+        assert_eq!(
+            a[0],
+            (
+                "Unit".to_string(),
+                "Requires".to_string(),
+                "a.service  b.service".to_string()
+            )
+        );
+        assert_eq!(a[1].0, "Service");
+        assert_eq!(exec_path(&a[1].2).as_deref(), Some("/usr/bin/foo"));
+        assert_eq!(exec_path(&a[2].2).as_deref(), Some("/usr/bin/bar"));
+        assert_eq!(exec_path("${FOO} --bar"), None);
+        assert_eq!(template_name("getty@tty1.service"), "getty@.service");
+    }
+
+    #[test]
+    fn resets_exec_commands_in_dropins() {
+        let root = root("systemd-dropins");
+        write(
+            &root,
+            "/usr/lib/systemd/system/foo.service",
+            "[Service]\nExecStart=/usr/bin/foo\n",
+        );
+        write(
+            &root,
+            "/etc/systemd/system/foo.service.d/override.conf",
+            "[Service]\nExecStart=\nExecStart=/usr/bin/foo-ng\n",
+        );
+
+        let nodes = scan(&root);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(deps(&nodes[0], EdgeType::Exec), ["/usr/bin/foo-ng"]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn masks_lower_precedence_units() {
+        let root = root("systemd-mask");
+        write(
+            &root,
+            "/usr/lib/systemd/system/foo.service",
+            "[Service]\nExecStart=/usr/bin/foo\n",
+        );
+        link(&root, "/etc/systemd/system/foo.service", "/dev/null");
+
+        assert!(scan(&root).is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn resolves_links_within_root() {
+        let root = root("systemd-links");
+        write(
+            &root,
+            "/usr/lib/systemd/system/systemd-networkd.service",
+            "[Service]\nExecStart=/usr/lib/systemd/systemd-networkd\n",
+        );
+        write(
+            &root,
+            "/opt/bar/bar.service",
+            "[Service]\nExecStart=/opt/bar/bin/bar\n",
+        );
+        write(
+            &root,
+            "/usr/lib/systemd/system/app.service",
+            "[Unit]\nRequires=dbus-org.freedesktop.network1.service bar.service\n",
+        );
+
+        // Absolute links point into the image, not at the host.
+        link(
+            &root,
+            "/etc/systemd/system/dbus-org.freedesktop.network1.service",
+            "/usr/lib/systemd/system/systemd-networkd.service",
+        );
+        link(
+            &root,
+            "/etc/systemd/system/bar.service",
+            "/opt/bar/bar.service",
+        );
+
+        let files = service_files(&root);
+        assert_eq!(
+            files.units.keys().collect::<Vec<_>>(),
+            ["app.service", "bar.service", "systemd-networkd.service"]
+        );
+        assert_eq!(
+            files.units["bar.service"],
+            [PathBuf::from(format!("{}/opt/bar/bar.service", root))]
+        );
+        assert_eq!(
+            files.aliases["dbus-org.freedesktop.network1.service"],
+            "systemd-networkd.service"
+        );
+
+        let nodes = scan(&root);
+        let node = |n: &str| nodes.iter().find(|x| x.name() == n).unwrap();
+        assert_eq!(
+            deps(node("app.service"), EdgeType::Requires),
+            ["systemd-networkd.service", "bar.service"]
+        );
+        assert_eq!(
+            deps(node("bar.service"), EdgeType::Exec),
+            ["/opt/bar/bin/bar"]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn relative_links_stay_within_root() {
+        let root = root("systemd-relative");
+        link(
+            &root,
+            "/etc/systemd/system/foo.service",
+            "../../../../../../../../foo.service",
+        );
+        let target = resolve_link(
+            &root,
+            Path::new(&format!("{}/etc/systemd/system/foo.service", root)),
+        );
+        assert_eq!(target, Some(PathBuf::from(format!("{}/foo.service", root))));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,303 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+};
+
+use rustworkx_core::petgraph::graph::{DiGraph, NodeIndex};
+use serde::Serialize;
+
+use crate::{
+    errors::BingraphError,
+    node::{BinNode, Dependency, EdgeType},
+    pe::LoaderTables,
+    resolver::{Resolution, Resolver},
+};
+
+/// A TreeEntry is a single dependency within a dependency tree, along with
+/// the dependencies it brings in itself.
+#[derive(Debug, Serialize)]
+pub struct TreeEntry {
+    name: String,
+
+    /// The resolved file, if the dependency could be found at all.
+    path: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<EdgeType>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule: Option<Resolution>,
+
+    /// The dependencies of this entry are listed elsewhere in the tree.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    duplicate: bool,
+
+    /// This entry depends back on one of its ancestors.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    cycle: bool,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<TreeEntry>,
+}
+
+/// List the files that could satisfy a dependency of `node`: its explicit
+/// candidate paths, the image or module directory where the loader looks
+/// there, and the default search directories. Earlier search directories come
+/// last, as the resolver prefers later candidates.
+fn candidates(node: &BinNode, dep: &Dependency, search_dirs: &[&str]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = dep.paths.iter().map(PathBuf::from).collect();
+    let own_dir = Path::new(node.absolute_path())
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let mut names = vec![dep.file_name().to_string()];
+    let mut dirs: Vec<PathBuf> = search_dirs.iter().rev().map(PathBuf::from).collect();
+    match node.node_type() {
+        t if t.is_portable() => {
+            names.push(dep.file_name().to_lowercase());
+            dirs.push(own_dir);
+        }
+        t if t.is_wasm() => {
+            names.push(format!("{}.wasm", dep.name));
+            dirs.push(own_dir);
+        }
+        _ => {}
+    }
+
+    for dir in dirs.iter() {
+        paths.extend(names.iter().map(|n| dir.join(n)));
+    }
+
+    paths.into_iter().filter(|p| p.is_file()).collect()
+}
+
+/// Load the file at `path` and every file that could satisfy its dependencies,
+/// transitively, without scanning anything else.
+fn load(path: &str, search_dirs: &[&str]) -> Result<DiGraph<BinNode, EdgeType>, BingraphError> {
+    let mut graph: DiGraph<BinNode, EdgeType> = DiGraph::new();
+    let mut loaded: HashSet<PathBuf> = HashSet::new();
+    let mut queue: VecDeque<NodeIndex> = VecDeque::new();
+
+    loaded.insert(PathBuf::from(path));
+    for node in BinNode::from_path(Path::new(path))? {
+        queue.push_back(graph.add_node(node));
+    }
+
+    while let Some(idx) = queue.pop_front() {
+        let mut found: Vec<PathBuf> = vec![];
+        for dep in graph[idx].get_dependencies() {
+            found.extend(candidates(&graph[idx], dep, search_dirs));
+        }
+
+        for candidate in found {
+            if !loaded.insert(candidate.clone()) {
+                continue;
+            }
+            match BinNode::from_path(&candidate) {
+                Ok(nodes) => {
+                    for node in nodes {
+                        queue.push_back(graph.add_node(node));
+                    }
+                }
+                Err(e) => println!("unable to create node at {:?}: {}", candidate, e),
+            }
+        }
+    }
+
+    Ok(graph)
+}
+
+struct TreeBuilder<'a> {
+    graph: &'a DiGraph<BinNode, EdgeType>,
+    resolver: Resolver<'a>,
+
+    /// Nodes whose dependencies have already been listed.
+    expanded: HashSet<NodeIndex>,
+
+    /// Nodes between the root and the entry being built.
+    ancestors: Vec<NodeIndex>,
+
+    /// Resolutions already made, as the same dependency recurs often.
+    resolved: HashMap<(NodeIndex, usize), Option<(NodeIndex, Resolution)>>,
+}
+
+impl TreeBuilder<'_> {
+    fn children(&mut self, idx: NodeIndex) -> Vec<TreeEntry> {
+        self.expanded.insert(idx);
+        self.ancestors.push(idx);
+
+        let node = &self.graph[idx];
+        let mut children: Vec<TreeEntry> = vec![];
+        for (i, dep) in node.get_dependencies().iter().enumerate() {
+            let resolution = *self
+                .resolved
+                .entry((idx, i))
+                .or_insert_with(|| self.resolver.resolve_with_rule(self.graph, node, dep));
+
+            let mut entry = TreeEntry {
+                name: dep.name.clone(),
+                path: None,
+                kind: Some(dep.kind),
+                rule: None,
+                duplicate: false,
+                cycle: false,
+                children: vec![],
+            };
+
+            if let Some((didx, rule)) = resolution {
+                entry.path = Some(self.graph[didx].absolute_path().to_string());
+                entry.rule = Some(rule);
+
+                if self.ancestors.contains(&didx) {
+                    entry.cycle = true;
+                } else if self.expanded.contains(&didx) {
+                    entry.duplicate = true;
+                } else {
+                    entry.children = self.children(didx);
+                }
+            }
+
+            children.push(entry);
+        }
+
+        self.ancestors.pop();
+        children
+    }
+}
+
+impl TreeEntry {
+    fn print(&self, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let mut line = match (&self.path, self.kind, self.rule) {
+            (Some(p), Some(k), Some(r)) => {
+                format!(
+                    "{}{} => {} ({}, {})",
+                    indent,
+                    self.name,
+                    p,
+                    k.name(),
+                    r.name()
+                )
+            }
+            (None, Some(k), _) => format!("{}{} => not found ({})", indent, self.name, k.name()),
+            _ => format!("{}{}", indent, self.path.as_deref().unwrap_or(&self.name)),
+        };
+
+        if self.cycle {
+            line.push_str(" [cycle]");
+        } else if self.duplicate {
+            line.push_str(" [listed above]");
+        }
+        println!("{}", line);
+
+        for child in self.children.iter() {
+            child.print(depth + 1);
+        }
+    }
+}
+
+/// Resolve the dependencies of a single file with the resolver used for the
+/// full graph, searching `lib_path` by default, and print them as a tree.
+/// Nothing is executed, so this is safe on untrusted files, unlike `ldd`.
+pub fn dependency_trees(
+    path: &str,
+    lib_path: &str,
+    loader: &LoaderTables,
+) -> Result<Vec<TreeEntry>, BingraphError> {
+    let search_dirs: Vec<&str> = lib_path.split(':').filter(|d| !d.is_empty()).collect();
+    let graph = load(path, &search_dirs)?;
+    let roots: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|idx| graph[*idx].absolute_path() == path)
+        .collect();
+
+    let mut builder = TreeBuilder {
+        graph: &graph,
+        resolver: Resolver::new(&graph, loader),
+        expanded: HashSet::new(),
+        ancestors: vec![],
+        resolved: HashMap::new(),
+    };
+
+    let mut trees: Vec<TreeEntry> = vec![];
+    for idx in roots {
+        builder.expanded.clear();
+        let tree = TreeEntry {
+            name: graph[idx].name(),
+            path: Some(graph[idx].absolute_path().to_string()),
+            kind: None,
+            rule: None,
+            duplicate: false,
+            cycle: false,
+            children: builder.children(idx),
+        };
+        tree.print(0);
+        trees.push(tree);
+    }
+
+    Ok(trees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/elf");
+
+    #[test]
+    fn lists_repeated_subtrees_once() {
+        let exe = format!("{}/exe", FIXTURES);
+        let trees = dependency_trees(
+            &exe,
+            &format!("/nonexistent:{}", FIXTURES),
+            &LoaderTables::default(),
+        )
+        .unwrap();
+        assert_eq!(trees.len(), 1);
+
+        let root = &trees[0];
+        assert_eq!(root.path.as_deref(), Some(exe.as_str()));
+        let names: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["liba.so", "libb.so"]);
+
+        // libb.so is expanded under liba.so first, then only referenced.
+        let liba = &root.children[0];
+        assert_eq!(liba.children.len(), 1);
+        assert_eq!(liba.children[0].name, "libb.so");
+        assert!(!liba.children[0].duplicate);
+        assert_eq!(liba.children[0].path, Some(format!("{}/libb.so", FIXTURES)));
+
+        let libb = &root.children[1];
+        assert!(libb.duplicate);
+        assert!(libb.children.is_empty());
+        assert_eq!(libb.kind, Some(EdgeType::Needed));
+    }
+
+    #[test]
+    fn reports_missing_dependencies() {
+        let exe = format!("{}/exe", FIXTURES);
+        let trees = dependency_trees(&exe, "/nonexistent", &LoaderTables::default()).unwrap();
+        let root = &trees[0];
+        assert_eq!(root.children.len(), 2);
+        assert!(root.children.iter().all(|c| c.path.is_none()));
+    }
+}
@@ -0,0 +1,414 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use crate::{
+    archive::ExportedSymbol,
+    errors::BingraphError,
+    node::{BinNode, Dependency, EdgeType, NodeType},
+};
+
+/// Every WebAssembly binary starts with this magic.
+pub const MAGIC: &[u8; 4] = b"\0asm";
+
+/// The version field of a core module, as opposed to a component.
+const CORE_VERSION: [u8; 4] = [1, 0, 0, 0];
+
+/// The layer field following the version of a component-model binary.
+const COMPONENT_LAYER: [u8; 2] = [1, 0];
+
+const SECTION_IMPORT: u8 = 2;
+const SECTION_EXPORT: u8 = 7;
+
+const COMPONENT_SECTION_IMPORT: u8 = 10;
+const COMPONENT_SECTION_EXPORT: u8 = 11;
+
+/// A minimal cursor over the WebAssembly binary encoding.
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn byte(&mut self) -> Result<u8, BingraphError> {
+        let b = *self
+            .data
+            .get(self.offset)
+            .ok_or_else(|| String::from("wasm: unexpected end of data"))?;
+        self.offset += 1;
+        Ok(b)
+    }
+
+    /// Read an unsigned LEB128 integer.
+    fn leb(&mut self) -> Result<u64, BingraphError> {
+        let mut result: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            if shift < 64 {
+                result |= u64::from(b & 0x7f) << shift;
+            }
+            if b & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], BingraphError> {
+        let end = self.offset.saturating_add(len);
+        let b = self
+            .data
+            .get(self.offset..end)
+            .ok_or_else(|| String::from("wasm: unexpected end of data"))?;
+        self.offset = end;
+        Ok(b)
+    }
+
+    fn name(&mut self) -> Result<String, BingraphError> {
+        let len = self.leb()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).to_string())
+    }
+
+    /// Skip over a limits structure, as used by tables and memories.
+    fn limits(&mut self) -> Result<(), BingraphError> {
+        let flags = self.byte()?;
+        self.leb()?;
+        if flags & 0x01 != 0 {
+            self.leb()?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the import section into a dependency per imported module name, with
+/// the imported fields recorded as the dependency's symbols.
+fn imports(section: &[u8]) -> Result<Vec<Dependency>, BingraphError> {
+    let mut r = Reader::new(section);
+    let mut deps: Vec<Dependency> = vec![];
+
+    for _ in 0..r.leb()? {
+        let module = r.name()?;
+        let field = r.name()?;
+
+        match r.byte()? {
+            // Function, tag.
+            0x00 => {
+                r.leb()?;
+            }
+            0x04 => {
+                r.byte()?;
+                r.leb()?;
+            }
+            // Table: reftype then limits.
+            0x01 => {
+                r.byte()?;
+                r.limits()?;
+            }
+            // Memory.
+            0x02 => r.limits()?,
+            // Global: valtype then mutability.
+            0x03 => {
+                r.byte()?;
+                r.byte()?;
+            }
+            k => return Err(format!("wasm: unknown import kind {:#x}", k).into()),
+        }
+
+        match deps.iter_mut().find(|d| d.name == module) {
+            Some(d) => d.symbols.push(field),
+            None => {
+                let mut d = Dependency::new(&module, EdgeType::Needed);
+                d.symbols.push(field);
+                deps.push(d);
+            }
+        }
+    }
+
+    Ok(deps)
+}
+
+/// Parse the export section into the names this module provides.
+fn exports(section: &[u8]) -> Result<Vec<ExportedSymbol>, BingraphError> {
+    let mut r = Reader::new(section);
+    let mut exports: Vec<ExportedSymbol> = vec![];
+
+    for _ in 0..r.leb()? {
+        let name = r.name()?;
+        r.byte()?;
+        r.leb()?;
+        exports.push(ExportedSymbol { name, weak: false });
+    }
+
+    Ok(exports)
+}
+
+/// Read the name of a component import or export, along with the version
+/// suffix that may follow it.
+fn component_name(r: &mut Reader) -> Result<String, BingraphError> {
+    match r.byte()? {
+        0x00 => r.name(),
+        0x01 => {
+            let name = r.name()?;
+            Ok(name + &r.name()?)
+        }
+        k => Err(format!("wasm: unknown component name kind {:#x}", k).into()),
+    }
+}
+
+/// Skip over the type a component import or export is described by.
+fn extern_desc(r: &mut Reader) -> Result<(), BingraphError> {
+    match r.byte()? {
+        // Core module, which carries an extra core sort byte.
+        0x00 => {
+            r.byte()?;
+            r.leb()?;
+        }
+        // Function, component and instance types.
+        0x01 | 0x04 | 0x05 => {
+            r.leb()?;
+        }
+        // Value and type bounds: a tag then an index or value type, or a
+        // bare tag for a resource type.
+        0x02 => {
+            r.byte()?;
+            r.leb()?;
+        }
+        0x03 => {
+            if r.byte()? == 0x00 {
+                r.leb()?;
+            }
+        }
+        k => return Err(format!("wasm: unknown extern kind {:#x}", k).into()),
+    }
+    Ok(())
+}
+
+/// Parse a component's import section. Interfaces are grouped into a
+/// dependency per package, such as `wasi:io@0.2.0` for the import of
+/// `wasi:io/streams@0.2.0`, with the full interface names as its symbols.
+fn component_imports(section: &[u8]) -> Result<Vec<Dependency>, BingraphError> {
+    let mut r = Reader::new(section);
+    let mut deps: Vec<Dependency> = vec![];
+
+    for _ in 0..r.leb()? {
+        let name = component_name(&mut r)?;
+        extern_desc(&mut r)?;
+
+        let package = match name.split_once('/') {
+            Some((package, rest)) => match rest.split_once('@') {
+                Some((_, version)) => format!("{}@{}", package, version),
+                None => package.to_string(),
+            },
+            None => name.clone(),
+        };
+
+        match deps.iter_mut().find(|d| d.name == package) {
+            Some(d) => d.symbols.push(name),
+            None => {
+                let mut d = Dependency::new(&package, EdgeType::Needed);
+                d.symbols.push(name);
+                deps.push(d);
+            }
+        }
+    }
+
+    Ok(deps)
+}
+
+/// Parse a component's export section into the names it provides.
+fn component_exports(section: &[u8]) -> Result<Vec<ExportedSymbol>, BingraphError> {
+    let mut r = Reader::new(section);
+    let mut exports: Vec<ExportedSymbol> = vec![];
+
+    for _ in 0..r.leb()? {
+        let name = component_name(&mut r)?;
+
+        // The sort of the exported item, core items carrying a second byte,
+        // then its index and an optional type ascription.
+        if r.byte()? == 0x00 {
+            r.byte()?;
+        }
+        r.leb()?;
+        if r.byte()? == 0x01 {
+            extern_desc(&mut r)?;
+        }
+
+        exports.push(ExportedSymbol { name, weak: false });
+    }
+
+    Ok(exports)
+}
+
+/// Create a node from a core WebAssembly module or a component. Only the
+/// top-level imports and exports of a component are read, not those of the
+/// modules and components nested within it.
+pub fn node(name: String, absolute_path: String, data: &[u8]) -> Result<BinNode, BingraphError> {
+    let component = if data.get(4..8) == Some(&CORE_VERSION) {
+        false
+    } else if data.get(6..8) == Some(&COMPONENT_LAYER) {
+        true
+    } else {
+        return Err(format!("{:?} is of an unknown wasm version", absolute_path).into());
+    };
+
+    let mut deps: Vec<Dependency> = vec![];
+    let mut exps: Vec<ExportedSymbol> = vec![];
+
+    let mut r = Reader::new(&data[8..]);
+    while !r.is_empty() {
+        let id = r.byte()?;
+        let len = r.leb()? as usize;
+        let section = r.bytes(len)?;
+
+        match id {
+            SECTION_IMPORT if !component => deps.extend(imports(section)?),
+            SECTION_EXPORT if !component => exps.extend(exports(section)?),
+            COMPONENT_SECTION_IMPORT if component => deps.extend(component_imports(section)?),
+            COMPONENT_SECTION_EXPORT if component => exps.extend(component_exports(section)?),
+            _ => {}
+        }
+    }
+
+    let t = if component {
+        NodeType::WasmComponent
+    } else {
+        NodeType::WasmModule
+    };
+    let mut node = BinNode::new(name, absolute_path, t);
+    node.set_dependencies(deps);
+    node.set_exports(exps);
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a length prefixed name.
+    fn name(s: &str) -> Vec<u8> {
+        let mut out = vec![s.len() as u8];
+        out.extend(s.as_bytes());
+        out
+    }
+
+    fn section(id: u8, contents: Vec<u8>) -> Vec<u8> {
+        let mut out = vec![id, contents.len() as u8];
+        out.extend(contents);
+        out
+    }
+
+    fn binary(version: [u8; 4], sections: Vec<Vec<u8>>) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend(version);
+        out.extend(sections.into_iter().flatten());
+        out
+    }
+
+    #[test]
+    fn core_module_imports_and_exports() {
+        let mut imports = vec![4];
+        imports.extend(name("env").into_iter().chain(name("malloc")));
+        imports.extend([0x00, 0x00]);
+        imports.extend(name("env").into_iter().chain(name("memory")));
+        imports.extend([0x02, 0x01, 0x01, 0x10]);
+        imports.extend(name("env").into_iter().chain(name("table")));
+        imports.extend([0x01, 0x70, 0x00, 0x01]);
+        imports.extend(name("wasi").into_iter().chain(name("errno")));
+        imports.extend([0x03, 0x7f, 0x00]);
+
+        let mut exports = vec![1];
+        exports.extend(name("run"));
+        exports.extend([0x00, 0x02]);
+
+        let data = binary(
+            CORE_VERSION,
+            vec![
+                section(1, vec![0]),
+                section(SECTION_IMPORT, imports),
+                section(SECTION_EXPORT, exports),
+            ],
+        );
+        let node = node("a.wasm".into(), "/w/a.wasm".into(), &data).unwrap();
+
+        assert_eq!(*node.node_type(), NodeType::WasmModule);
+        let deps = node.get_dependencies();
+        assert_eq!(deps.len(), 2);
+        assert_eq!(deps[0].name, "env");
+        assert_eq!(deps[0].symbols, vec!["malloc", "memory", "table"]);
+        assert_eq!(deps[1].name, "wasi");
+        assert_eq!(node.exports()[0].name, "run");
+    }
+
+    #[test]
+    fn component_imports_and_exports() {
+        let mut imports = vec![3];
+        imports.push(0x00);
+        imports.extend(name("wasi:io/streams@0.2.0"));
+        imports.extend([0x05, 0x00]);
+        imports.push(0x00);
+        imports.extend(name("wasi:io/error@0.2.0"));
+        imports.extend([0x05, 0x01]);
+        imports.push(0x00);
+        imports.extend(name("config"));
+        imports.extend([0x02, 0x01, 0x73]);
+
+        let mut exports = vec![2];
+        exports.push(0x00);
+        exports.extend(name("wasi:cli/run@0.2.0"));
+        exports.extend([0x05, 0x02, 0x00]);
+        exports.push(0x00);
+        exports.extend(name("core"));
+        exports.extend([0x00, 0x11, 0x00, 0x01, 0x00, 0x11, 0x00]);
+
+        let data = binary(
+            [0x0d, 0x00, 0x01, 0x00],
+            vec![
+                section(1, vec![0; 4]),
+                section(COMPONENT_SECTION_IMPORT, imports),
+                section(COMPONENT_SECTION_EXPORT, exports),
+            ],
+        );
+        let node = node("c.wasm".into(), "/w/c.wasm".into(), &data).unwrap();
+
+        assert_eq!(*node.node_type(), NodeType::WasmComponent);
+        let deps = node.get_dependencies();
+        assert_eq!(deps.len(), 2);
+        assert_eq!(deps[0].name, "wasi:io@0.2.0");
+        assert_eq!(
+            deps[0].symbols,
+            vec!["wasi:io/streams@0.2.0", "wasi:io/error@0.2.0"]
+        );
+        assert_eq!(deps[1].name, "config");
+
+        let exports: Vec<&str> = node.exports().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(exports, vec!["wasi:cli/run@0.2.0", "core"]);
+    }
+
+    #[test]
+    fn unknown_version() {
+        assert!(node("x".into(), "/x".into(), &binary([2, 0, 0, 0], vec![])).is_err());
+    }
+}
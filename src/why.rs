@@ -0,0 +1,362 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use serde::Serialize;
+
+use crate::{
+    errors::BingraphError,
+    graph::BinGraph,
+    node::{BinNode, EdgeType},
+};
+
+/// A Hop is a single edge along a path, with the symbols that justify it.
+#[derive(Debug, Serialize, Clone)]
+pub struct Hop {
+    source: String,
+    target: String,
+    kind: EdgeType,
+    symbols: Vec<String>,
+}
+
+/// A WhyReport explains how one node comes to depend upon another.
+#[derive(Debug, Serialize)]
+pub struct WhyReport {
+    from: Vec<String>,
+    to: Vec<String>,
+
+    /// The shortest path, empty if there is none.
+    shortest: Vec<Hop>,
+
+    /// Every simple path within the depth bound, only when requested.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    paths: Vec<Vec<Hop>>,
+
+    #[serde(skip)]
+    path_nodes: HashSet<usize>,
+}
+
+/// Bounds on the enumeration of all simple paths.
+pub struct PathBounds {
+    pub max_depth: usize,
+    pub max_paths: usize,
+}
+
+/// Find the symbols the source of an edge needs from its target. Imports
+/// that name their symbols, as on PE and WebAssembly, are used as they are.
+/// Otherwise these are the undefined symbols of the source that the target
+/// exports.
+fn justify(source: &BinNode, target: &BinNode, kind: EdgeType) -> Vec<String> {
+    let exported: HashSet<&str> = target.exports().iter().map(|e| e.name.as_str()).collect();
+
+    let named = source.get_dependencies().iter().find(|d| {
+        d.kind == kind
+            && !d.symbols.is_empty()
+            && (d.file_name().eq_ignore_ascii_case(&target.name())
+                || d.symbols.iter().all(|s| exported.contains(s.as_str())))
+    });
+    if let Some(dep) = named {
+        return dep.symbols.clone();
+    }
+
+    let undefined = source
+        .imports()
+        .iter()
+        .chain(source.members().iter().flat_map(|m| m.undefined_symbols()));
+    let symbols: BTreeSet<String> = undefined
+        .filter(|s| exported.contains(s.as_str()))
+        .cloned()
+        .collect();
+    symbols.into_iter().collect()
+}
+
+impl WhyReport {
+    /// Find the paths from any node matching `from` to any node matching `to`.
+    pub fn new(
+        g: &BinGraph,
+        from: &str,
+        to: &str,
+        bounds: Option<PathBounds>,
+    ) -> Result<Self, BingraphError> {
+        let nodes = g.nodes();
+        let sources = g.find_nodes(from);
+        let targets: BTreeSet<usize> = g.find_nodes(to).into_iter().collect();
+        if sources.is_empty() {
+            return Err(format!("no node in the graph matches {}", from).into());
+        }
+        if targets.is_empty() {
+            return Err(format!("no node in the graph matches {}", to).into());
+        }
+
+        let mut out: Vec<Vec<(usize, usize)>> = vec![vec![]; nodes.len()];
+        for (i, e) in g.edges().iter().enumerate() {
+            out[e.source_idx()].push((e.target_idx(), i));
+        }
+
+        // Breadth first from every source at once, remembering the edge each
+        // node was first reached through.
+        let mut via: Vec<Option<usize>> = vec![None; nodes.len()];
+        let mut seen: HashSet<usize> = sources.iter().copied().collect();
+        let mut queue: VecDeque<usize> = sources.iter().copied().collect();
+        let mut reached: Option<usize> = None;
+        while let Some(idx) = queue.pop_front() {
+            if targets.contains(&idx) {
+                reached = Some(idx);
+                break;
+            }
+            for (next, e) in out[idx].iter() {
+                if seen.insert(*next) {
+                    via[*next] = Some(*e);
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        let mut shortest_edges: Vec<usize> = vec![];
+        let mut at = reached;
+        while let Some(e) = at.and_then(|i| via[i]) {
+            shortest_edges.push(e);
+            at = Some(g.edges()[e].source_idx());
+        }
+        shortest_edges.reverse();
+
+        let mut all_edges: Vec<Vec<usize>> = vec![];
+        if let Some(ref b) = bounds {
+            for s in sources.iter() {
+                let mut on_path: Vec<usize> = vec![*s];
+                let mut edges: Vec<usize> = vec![];
+                simple_paths(&out, &targets, b, &mut on_path, &mut edges, &mut all_edges);
+            }
+        }
+
+        let mut path_nodes: HashSet<usize> = HashSet::new();
+        for e in shortest_edges.iter().chain(all_edges.iter().flatten()) {
+            path_nodes.insert(g.edges()[*e].source_idx());
+            path_nodes.insert(g.edges()[*e].target_idx());
+        }
+
+        // Symbols are not kept for every node, so read them for the nodes
+        // along the paths only.
+        let with_symbols: HashMap<usize, BinNode> = path_nodes
+            .iter()
+            .map(|i| (*i, nodes[*i].with_symbols()))
+            .collect();
+        let hops = |edges: &[usize]| -> Vec<Hop> {
+            edges
+                .iter()
+                .map(|i| {
+                    let e = &g.edges()[*i];
+                    let (s, t) = (
+                        &with_symbols[&e.source_idx()],
+                        &with_symbols[&e.target_idx()],
+                    );
+                    Hop {
+                        source: s.absolute_path().to_string(),
+                        target: t.absolute_path().to_string(),
+                        kind: e.kind(),
+                        symbols: justify(s, t, e.kind()),
+                    }
+                })
+                .collect()
+        };
+
+        let report = Self {
+            from: sources
+                .iter()
+                .map(|i| nodes[*i].absolute_path().to_string())
+                .collect(),
+            to: targets
+                .iter()
+                .map(|i| nodes[*i].absolute_path().to_string())
+                .collect(),
+            shortest: hops(&shortest_edges),
+            paths: all_edges.iter().map(|p| hops(p)).collect(),
+            path_nodes,
+        };
+        report.print(reached.is_some() || sources.iter().any(|s| targets.contains(s)));
+        Ok(report)
+    }
+
+    fn print(&self, found: bool) {
+        if !found {
+            println!("no path from {:?} to {:?}", self.from, self.to);
+            return;
+        }
+
+        println!("shortest path, {} hops:", self.shortest.len());
+        for hop in self.shortest.iter() {
+            let mut symbols = hop
+                .symbols
+                .iter()
+                .take(5)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ");
+            if hop.symbols.len() > 5 {
+                symbols.push_str(&format!(", and {} more", hop.symbols.len() - 5));
+            }
+            println!(
+                "  {} -> {} ({}: {})",
+                hop.source,
+                hop.target,
+                hop.kind.name(),
+                symbols
+            );
+        }
+
+        if !self.paths.is_empty() {
+            println!("{} simple paths within the depth bound", self.paths.len());
+        }
+    }
+
+    /// Indices of every node along the reported paths.
+    pub fn path_nodes(&self) -> &HashSet<usize> {
+        &self.path_nodes
+    }
+}
+
+/// Extend the current path depth first, recording it whenever it reaches a
+/// target. Paths stop at the first target they reach.
+fn simple_paths(
+    out: &[Vec<(usize, usize)>],
+    targets: &BTreeSet<usize>,
+    bounds: &PathBounds,
+    on_path: &mut Vec<usize>,
+    edges: &mut Vec<usize>,
+    paths: &mut Vec<Vec<usize>>,
+) {
+    let Some(&idx) = on_path.last() else {
+        return;
+    };
+
+    for (next, e) in out[idx].iter() {
+        if paths.len() >= bounds.max_paths {
+            return;
+        }
+        if on_path.contains(next) {
+            continue;
+        }
+
+        edges.push(*e);
+        if targets.contains(next) {
+            paths.push(edges.clone());
+        } else if edges.len() < bounds.max_depth {
+            on_path.push(*next);
+            simple_paths(out, targets, bounds, on_path, edges, paths);
+            on_path.pop();
+        }
+        edges.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{Dependency, NodeType};
+
+    /// exe -> a -> c, exe -> b -> c, exe -> d -> e -> c, with a second,
+    /// unreachable node also named c.
+    fn diamond() -> BinGraph {
+        let nodes = [
+            ("exe", "/exe", &["a", "b", "d"][..]),
+            ("a", "/a", &["c"]),
+            ("b", "/b", &["c"]),
+            ("d", "/d", &["e"]),
+            ("e", "/e", &["c"]),
+            ("c", "/lib32/c", &[]),
+            ("c", "/c", &[]),
+        ]
+        .iter()
+        .map(|(name, path, deps)| {
+            let mut node = BinNode::new(name.to_string(), path.to_string(), NodeType::ELFLibrary);
+            node.set_dependencies(
+                deps.iter()
+                    .map(|d| {
+                        let mut dep = Dependency::new(d, EdgeType::Needed);
+                        dep.symbols = vec![format!("{}_init", d)];
+                        dep
+                    })
+                    .collect(),
+            );
+            node
+        })
+        .collect();
+        BinGraph::from_nodes(nodes).unwrap()
+    }
+
+    fn path(hops: &[Hop]) -> Vec<&str> {
+        let mut path: Vec<&str> = hops.iter().map(|h| h.source.as_str()).collect();
+        path.extend(hops.last().map(|h| h.target.as_str()));
+        path
+    }
+
+    #[test]
+    fn finds_shortest_path() {
+        let g = diamond();
+        let report = WhyReport::new(&g, "exe", "c", None).unwrap();
+
+        assert_eq!(path(&report.shortest), ["/exe", "/a", "/c"]);
+        assert_eq!(report.shortest[0].symbols, ["a_init"]);
+        assert!(report.paths.is_empty());
+        assert_eq!(report.path_nodes, HashSet::from([0, 1, 6]));
+    }
+
+    #[test]
+    fn enumerates_bounded_simple_paths() {
+        let g = diamond();
+        let bounds = |max_depth, max_paths| {
+            Some(PathBounds {
+                max_depth,
+                max_paths,
+            })
+        };
+
+        let report = WhyReport::new(&g, "exe", "c", bounds(3, 10)).unwrap();
+        let mut paths: Vec<Vec<&str>> = report.paths.iter().map(|p| path(p)).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                vec!["/exe", "/a", "/c"],
+                vec!["/exe", "/b", "/c"],
+                vec!["/exe", "/d", "/e", "/c"],
+            ]
+        );
+
+        let report = WhyReport::new(&g, "exe", "c", bounds(2, 10)).unwrap();
+        assert_eq!(report.paths.len(), 2);
+
+        let report = WhyReport::new(&g, "exe", "c", bounds(3, 1)).unwrap();
+        assert_eq!(report.paths.len(), 1);
+    }
+
+    #[test]
+    fn reports_targets_in_node_order() {
+        let g = diamond();
+        for _ in 0..10 {
+            let report = WhyReport::new(&g, "exe", "c", None).unwrap();
+            assert_eq!(report.to, ["/lib32/c", "/c"]);
+        }
+        assert!(WhyReport::new(&g, "c", "exe", None)
+            .unwrap()
+            .shortest
+            .is_empty());
+        assert!(WhyReport::new(&g, "exe", "missing", None).is_err());
+    }
+}
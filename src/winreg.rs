@@ -0,0 +1,529 @@
+/*
+*	Copyright (C) 2025 Kendall Tauser
+*
+*	This program is free software; you can redistribute it and/or modify
+*	it under the terms of the GNU General Public License as published by
+*	the Free Software Foundation; either version 2 of the License, or
+*	(at your option) any later version.
+*
+*	This program is distributed in the hope that it will be useful,
+*	but WITHOUT ANY WARRANTY; without even the implied warranty of
+*	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*	GNU General Public License for more details.
+*
+*	You should have received a copy of the GNU General Public License along
+*	with this program; if not, write to the Free Software Foundation, Inc.,
+*	51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+use std::{collections::HashMap, fs, iter::Peekable, path::Path, str::CharIndices};
+
+use crate::errors::BingraphError;
+
+/// Cell offsets are relative to the first hive bin, which follows the
+/// 4096 byte base block.
+const HBIN_START: usize = 4096;
+
+/// Offset of the root key cell within the base block.
+const ROOT_CELL_OFFSET: usize = 0x24;
+
+/// Key and value names stored as Latin-1 rather than UTF-16.
+const KEY_COMP_NAME: u16 = 0x20;
+const VALUE_COMP_NAME: u16 = 0x1;
+
+/// Index roots only ever point at leaf lists, so a valid hive nests them a
+/// single level deep. Deeper or looping lists are malformed and cut off.
+const MAX_INDEX_DEPTH: u32 = 1;
+
+/// A value's data is stored in place of its data offset.
+const DATA_INLINE: u32 = 0x8000_0000;
+
+/// The first line of the text registry files Wine keeps in a prefix.
+const WINE_REGISTRY_HEADER: &str = "WINE REGISTRY Version 2";
+
+const REG_SZ: u32 = 1;
+const REG_EXPAND_SZ: u32 = 2;
+const REG_DWORD: u32 = 4;
+
+/// The data of a registry value, for the types bingraph reads.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegValue {
+    String(String),
+    Dword(u32),
+    Other,
+}
+
+/// A Hive is an offline Windows registry hive file, such as the SYSTEM hive
+/// of a mounted Windows image, read just far enough to look up values.
+pub struct Hive {
+    data: Vec<u8>,
+}
+
+fn decode_name(bytes: &[u8], compressed: bool) -> String {
+    if compressed {
+        bytes.iter().map(|b| *b as char).collect()
+    } else {
+        decode_utf16(bytes)
+    }
+}
+
+fn decode_utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+impl Hive {
+    pub fn open(path: &Path) -> Result<Self, BingraphError> {
+        Self::from_bytes(fs::read(path)?)
+            .ok_or_else(|| format!("{:?} is not a registry hive", path).into())
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Option<Self> {
+        data.starts_with(b"regf").then_some(Self { data })
+    }
+
+    /// Return the contents of the cell at a hive offset, without its size.
+    fn cell(&self, offset: u32) -> Option<&[u8]> {
+        let start = HBIN_START + offset as usize;
+        let size = u32_at(&self.data, start)? as i32;
+        self.data
+            .get(start + 4..start + size.unsigned_abs() as usize)
+    }
+
+    /// Find a key by the names of the keys leading to it from the root key,
+    /// compared case-insensitively, returning its cell offset.
+    pub fn key(&self, path: &[&str]) -> Option<u32> {
+        let root = u32_at(&self.data, ROOT_CELL_OFFSET)?;
+        path.iter()
+            .try_fold(root, |key, name| self.subkey(key, name))
+    }
+
+    fn subkey(&self, key: u32, name: &str) -> Option<u32> {
+        let nk = self.cell(key).filter(|c| c.starts_with(b"nk"))?;
+        let mut subkeys: Vec<u32> = vec![];
+        self.list_entries(u32_at(nk, 0x1c)?, 0, &mut subkeys);
+        subkeys.into_iter().find(|k| {
+            self.key_name(*k)
+                .is_some_and(|n| n.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// Collect the key offsets of a subkey list, following index roots up to
+    /// `MAX_INDEX_DEPTH` levels.
+    fn list_entries(&self, list: u32, depth: u32, out: &mut Vec<u32>) {
+        let Some(cell) = self.cell(list) else {
+            return;
+        };
+        let count = u16_at(cell, 2).unwrap_or_default() as usize;
+        let (stride, is_root) = match cell.get(0..2) {
+            Some(b"lf") | Some(b"lh") => (8, false),
+            Some(b"li") => (4, false),
+            Some(b"ri") if depth < MAX_INDEX_DEPTH => (4, true),
+            _ => return,
+        };
+
+        for i in 0..count {
+            let Some(offset) = u32_at(cell, 4 + i * stride) else {
+                return;
+            };
+            if is_root {
+                self.list_entries(offset, depth + 1, out);
+            } else {
+                out.push(offset);
+            }
+        }
+    }
+
+    fn key_name(&self, key: u32) -> Option<String> {
+        let nk = self.cell(key).filter(|c| c.starts_with(b"nk"))?;
+        let flags = u16_at(nk, 2)?;
+        let len = u16_at(nk, 0x48)? as usize;
+        Some(decode_name(
+            nk.get(0x4c..0x4c + len)?,
+            flags & KEY_COMP_NAME != 0,
+        ))
+    }
+
+    /// Return the named values of a key.
+    pub fn values(&self, key: u32) -> Vec<(String, RegValue)> {
+        let Some(nk) = self.cell(key).filter(|c| c.starts_with(b"nk")) else {
+            return vec![];
+        };
+        let count = u32_at(nk, 0x24).unwrap_or_default() as usize;
+        let Some(list) = u32_at(nk, 0x28).and_then(|l| self.cell(l)) else {
+            return vec![];
+        };
+
+        (0..count)
+            .filter_map(|i| self.value(u32_at(list, i * 4)?))
+            .collect()
+    }
+
+    fn value(&self, offset: u32) -> Option<(String, RegValue)> {
+        let vk = self.cell(offset).filter(|c| c.starts_with(b"vk"))?;
+        let name_len = u16_at(vk, 2)? as usize;
+        let size = u32_at(vk, 4)?;
+        let data_type = u32_at(vk, 0xc)?;
+        let flags = u16_at(vk, 0x10)?;
+        let name = decode_name(vk.get(0x14..0x14 + name_len)?, flags & VALUE_COMP_NAME != 0);
+
+        let data = if size & DATA_INLINE != 0 {
+            vk.get(8..8 + (size & !DATA_INLINE).min(4) as usize)?
+        } else {
+            self.cell(u32_at(vk, 8)?)?.get(..size as usize)?
+        };
+
+        let value = match data_type {
+            REG_SZ | REG_EXPAND_SZ => RegValue::String(decode_utf16(data)),
+            REG_DWORD => RegValue::Dword(u32_at(data, 0)?),
+            _ => RegValue::Other,
+        };
+        Some((name, value))
+    }
+}
+
+/// A RegFile is a registry branch saved as text, the way Wine keeps the
+/// registry of a prefix in `system.reg` and `user.reg`.
+pub struct RegFile {
+    /// The values of every key, by its lowercased path joined with `\`.
+    keys: HashMap<String, Vec<(String, RegValue)>>,
+}
+
+/// Read up to `max` further digits of an escaped character code whose
+/// leading digits have the value `n`.
+fn escaped_code(chars: &mut Peekable<CharIndices>, radix: u32, max: usize, n: u32) -> Option<char> {
+    let mut n = n;
+    for _ in 0..max {
+        match chars.peek().and_then(|(_, d)| d.to_digit(radix)) {
+            Some(d) => n = n * radix + d,
+            None => break,
+        }
+        chars.next();
+    }
+    char::from_u32(n)
+}
+
+/// Parse a quoted string at the start of `s`, undoing Wine's escapes, and
+/// return it along with the remainder after the closing quote.
+fn unquote(s: &str) -> Option<(String, &str)> {
+    let body = s.strip_prefix('"')?;
+    let mut chars = body.char_indices().peekable();
+    let mut out = String::new();
+
+    while let Some((i, c)) = chars.next() {
+        let c = match c {
+            '"' => return Some((out, &body[i + 1..])),
+            '\\' => match chars.next()?.1 {
+                'a' => '\x07',
+                'b' => '\x08',
+                'e' => '\x1b',
+                'f' => '\x0c',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'v' => '\x0b',
+                'x' => escaped_code(&mut chars, 16, 4, 0)?,
+                d @ '0'..='7' => escaped_code(&mut chars, 8, 2, d as u32 - '0' as u32)?,
+                e => e,
+            },
+            c => c,
+        };
+        out.push(c);
+    }
+
+    None
+}
+
+/// Parse the data of a value, the text following its `=`.
+fn parse_data(data: &str) -> RegValue {
+    if let Some(dword) = data.strip_prefix("dword:") {
+        return u32::from_str_radix(dword.trim(), 16)
+            .map(RegValue::Dword)
+            .unwrap_or(RegValue::Other);
+    }
+
+    // Expandable strings are written as `str(2):"..."`.
+    let quoted = data.strip_prefix("str(2):").unwrap_or(data);
+    match unquote(quoted) {
+        Some((s, _)) => RegValue::String(s),
+        None => RegValue::Other,
+    }
+}
+
+impl RegFile {
+    pub fn open(path: &Path) -> Result<Self, BingraphError> {
+        let text = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+        Self::parse(&text).ok_or_else(|| format!("{:?} is not a Wine registry file", path).into())
+    }
+
+    /// Parse the text of a Wine registry file. Key paths are relative to the
+    /// branch the file holds, such as `HKEY_LOCAL_MACHINE` for `system.reg`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        if lines.next()?.trim() != WINE_REGISTRY_HEADER {
+            return None;
+        }
+
+        let mut keys: HashMap<String, Vec<(String, RegValue)>> = HashMap::new();
+        let mut current: Option<String> = None;
+        for line in lines {
+            // Key lines end with the last modification time after the name,
+            // and separate path components with an escaped backslash.
+            if let Some(key) = line.strip_prefix('[') {
+                current = key.rsplit_once(']').map(|(k, _)| {
+                    k.split("\\\\")
+                        .map(|c| c.to_lowercase())
+                        .collect::<Vec<String>>()
+                        .join("\\")
+                });
+                if let Some(k) = &current {
+                    keys.entry(k.clone()).or_default();
+                }
+                continue;
+            }
+
+            // Options such as `#time=` and comments are skipped, as are the
+            // continuation lines of long hex values.
+            let Some(values) = current.as_ref().and_then(|k| keys.get_mut(k)) else {
+                continue;
+            };
+            let (name, rest) = match line.strip_prefix('@') {
+                Some(rest) => (String::new(), rest),
+                None => match unquote(line) {
+                    Some(named) => named,
+                    None => continue,
+                },
+            };
+            if let Some(data) = rest.strip_prefix('=') {
+                values.push((name, parse_data(data)));
+            }
+        }
+
+        Some(Self { keys })
+    }
+
+    /// Return the named values of the key at a path, compared
+    /// case-insensitively, or nothing when there is no such key.
+    pub fn values(&self, path: &[&str]) -> Option<Vec<(String, RegValue)>> {
+        self.keys.get(&path.join("\\").to_lowercase()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Appends cells to a hive, returning their offsets.
+    struct Builder {
+        data: Vec<u8>,
+    }
+
+    impl Builder {
+        fn new() -> Self {
+            let mut data = vec![0; HBIN_START + 32];
+            data[..4].copy_from_slice(b"regf");
+            data[HBIN_START..HBIN_START + 4].copy_from_slice(b"hbin");
+            Self { data }
+        }
+
+        fn cell(&mut self, contents: &[u8]) -> u32 {
+            let offset = (self.data.len() - HBIN_START) as u32;
+            let size = (contents.len() + 4).next_multiple_of(8);
+            self.data.extend((-(size as i32)).to_le_bytes());
+            self.data.extend(contents);
+            self.data.resize(HBIN_START + offset as usize + size, 0);
+            offset
+        }
+
+        fn key(&mut self, name: &str, subkeys: &[u32], values: &[u32]) -> u32 {
+            let list = if subkeys.is_empty() {
+                u32::MAX
+            } else {
+                let mut lf = b"lf".to_vec();
+                lf.extend((subkeys.len() as u16).to_le_bytes());
+                for k in subkeys {
+                    lf.extend(k.to_le_bytes());
+                    lf.extend([0; 4]);
+                }
+                self.cell(&lf)
+            };
+            let value_list = if values.is_empty() {
+                u32::MAX
+            } else {
+                let list: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+                self.cell(&list)
+            };
+
+            let mut nk = vec![0; 0x4c];
+            nk[..2].copy_from_slice(b"nk");
+            nk[2..4].copy_from_slice(&KEY_COMP_NAME.to_le_bytes());
+            nk[0x14..0x18].copy_from_slice(&(subkeys.len() as u32).to_le_bytes());
+            nk[0x1c..0x20].copy_from_slice(&list.to_le_bytes());
+            nk[0x24..0x28].copy_from_slice(&(values.len() as u32).to_le_bytes());
+            nk[0x28..0x2c].copy_from_slice(&value_list.to_le_bytes());
+            nk[0x48..0x4a].copy_from_slice(&(name.len() as u16).to_le_bytes());
+            nk.extend(name.as_bytes());
+            self.cell(&nk)
+        }
+
+        fn value(&mut self, name: &str, data_type: u32, data: &[u8]) -> u32 {
+            let (size, offset) = if data.len() <= 4 {
+                let mut inline = [0; 4];
+                inline[..data.len()].copy_from_slice(data);
+                (data.len() as u32 | DATA_INLINE, u32::from_le_bytes(inline))
+            } else {
+                (data.len() as u32, self.cell(data))
+            };
+
+            let mut vk = vec![0; 0x14];
+            vk[..2].copy_from_slice(b"vk");
+            vk[2..4].copy_from_slice(&(name.len() as u16).to_le_bytes());
+            vk[4..8].copy_from_slice(&size.to_le_bytes());
+            vk[8..12].copy_from_slice(&offset.to_le_bytes());
+            vk[0xc..0x10].copy_from_slice(&data_type.to_le_bytes());
+            vk[0x10..0x12].copy_from_slice(&VALUE_COMP_NAME.to_le_bytes());
+            vk.extend(name.as_bytes());
+            self.cell(&vk)
+        }
+
+        fn finish(mut self, root: u32) -> Hive {
+            self.data[ROOT_CELL_OFFSET..ROOT_CELL_OFFSET + 4].copy_from_slice(&root.to_le_bytes());
+            Hive::from_bytes(self.data).unwrap()
+        }
+    }
+
+    fn utf16z(s: &str) -> Vec<u8> {
+        s.encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    #[test]
+    fn values_of_nested_key() {
+        let mut b = Builder::new();
+        let current = b.value("Current", REG_DWORD, &2u32.to_le_bytes());
+        let select = b.key("Select", &[], &[current]);
+        let kernel32 = b.value("kernel32", REG_SZ, &utf16z("kernel32.dll"));
+        let dir = b.value(
+            "DllDirectory",
+            REG_EXPAND_SZ,
+            &utf16z("%SystemRoot%\\system32"),
+        );
+        let known = b.key("KnownDLLs", &[], &[kernel32, dir]);
+        let session = b.key("Session Manager", &[known], &[]);
+        let root = b.key("ROOT", &[select, session], &[]);
+        let hive = b.finish(root);
+
+        let select = hive.key(&["select"]).unwrap();
+        assert_eq!(
+            hive.values(select),
+            vec![("Current".to_string(), RegValue::Dword(2))]
+        );
+
+        let known = hive.key(&["Session Manager", "KnownDLLs"]).unwrap();
+        assert_eq!(
+            hive.values(known),
+            vec![
+                (
+                    "kernel32".to_string(),
+                    RegValue::String("kernel32.dll".to_string())
+                ),
+                (
+                    "DllDirectory".to_string(),
+                    RegValue::String("%SystemRoot%\\system32".to_string())
+                ),
+            ]
+        );
+
+        assert!(hive.key(&["Session Manager", "Missing"]).is_none());
+        assert!(Hive::from_bytes(b"not a hive".to_vec()).is_none());
+    }
+
+    #[test]
+    fn stops_at_nested_index_roots() {
+        let mut b = Builder::new();
+        let child = b.key("Child", &[], &[]);
+        let li = b.cell(&[b"li".as_slice(), &1u16.to_le_bytes(), &child.to_le_bytes()].concat());
+
+        // An index root listing the leaf list, and one listing itself.
+        let ri = b.cell(&[b"ri".as_slice(), &1u16.to_le_bytes(), &li.to_le_bytes()].concat());
+        let looping = b.data.len() - HBIN_START;
+        let self_ri = b.cell(
+            &[
+                b"ri".as_slice(),
+                &2u16.to_le_bytes(),
+                &(looping as u32).to_le_bytes(),
+                &ri.to_le_bytes(),
+            ]
+            .concat(),
+        );
+        let hive = b.finish(0);
+
+        let mut keys = vec![];
+        hive.list_entries(ri, 0, &mut keys);
+        assert_eq!(keys, [child]);
+
+        keys.clear();
+        hive.list_entries(self_ri, 0, &mut keys);
+        assert!(keys.is_empty());
+    }
+
+    #[test]
+    fn parses_wine_registry_text() {
+        let reg = RegFile::parse(concat!(
+            "WINE REGISTRY Version 2\n",
+            ";; All keys relative to \\\\Machine\n",
+            "\n",
+            "#arch=win64\n",
+            "\n",
+            "[System\\\\Select] 1700000000\n",
+            "#time=1da0000000000000\n",
+            "\"Current\"=dword:00000002\n",
+            "\n",
+            "[Software\\\\Wine\\\\Drives] 1700000000\n",
+            "@=\"default\"\n",
+            "\"c:\"=\"hd\"\n",
+            "\"Quoted \\\"name\\\"\"=\"C:\\\\windows\\\\caf\\x00e9\"\n",
+            "\"Binary\"=hex:01,02,\\\n",
+            "  03,04\n",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            reg.values(&["system", "SELECT"]),
+            Some(vec![("Current".to_string(), RegValue::Dword(2))])
+        );
+        assert_eq!(
+            reg.values(&["Software", "Wine", "Drives"]),
+            Some(vec![
+                (String::new(), RegValue::String("default".to_string())),
+                ("c:".to_string(), RegValue::String("hd".to_string())),
+                (
+                    "Quoted \"name\"".to_string(),
+                    RegValue::String("C:\\windows\\caf\u{e9}".to_string())
+                ),
+                ("Binary".to_string(), RegValue::Other),
+            ])
+        );
+        assert!(reg.values(&["Software", "Missing"]).is_none());
+        assert!(RegFile::parse("REGEDIT4\n").is_none());
+    }
+}
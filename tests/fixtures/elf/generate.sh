@@ -0,0 +1,61 @@
+#!/bin/sh
+# Regenerate the minimal ELF fixtures used by the unit tests. Nothing links
+# against libc, so the objects hold only the symbols and dynamic entries
+# bingraph reads.
+set -e
+cd "$(dirname "$0")"
+work=$(mktemp -d)
+trap 'rm -rf "$work"' EXIT
+
+CFLAGS="-Os -fPIC -fno-asynchronous-unwind-tables -nostdlib"
+LDFLAGS="-shared -Wl,-z,noseparate-code,--hash-style=gnu,--build-id=none -s"
+
+# libb.so exports a strong and a weak symbol, and keeps one hidden.
+cat > "$work/b.c" <<'C'
+int b_value(void) { return 1; }
+__attribute__((weak)) int b_hook(void) { return 0; }
+__attribute__((visibility("hidden"))) int b_hidden(void) { return 2; }
+C
+gcc $CFLAGS $LDFLAGS -Wl,-soname,libb.so -o libb.so "$work/b.c"
+
+# liba.so needs libb.so and imports one of its symbols.
+cat > "$work/a.c" <<'C'
+extern int b_value(void);
+int a_value(void) { return b_value() + 1; }
+C
+gcc $CFLAGS $LDFLAGS -Wl,-soname,liba.so -o liba.so "$work/a.c" -L. -lb
+
+# exe needs both, so libb.so is reached twice.
+cat > "$work/exe.c" <<'C'
+extern int a_value(void);
+extern int b_value(void);
+void _start(void) { a_value(); b_value(); }
+C
+gcc $CFLAGS -fno-pie -no-pie -Wl,-z,noseparate-code,--build-id=none -s \
+    -o exe "$work/exe.c" -L. -la -lb
+
+# libfilter.so filters libb.so, with auxiliary and audit libraries.
+cat > "$work/filter.c" <<'C'
+int filter_value(void) { return 3; }
+C
+gcc $CFLAGS $LDFLAGS -Wl,-soname,libfilter.so \
+    -Wl,--filter=libb.so -Wl,--auxiliary=libaux.so \
+    -Wl,--depaudit=libaudit.so:libaudit2.so \
+    -o libfilter.so "$work/filter.c"
+
+# libstatic.a holds two objects, one satisfying the other.
+cat > "$work/strong.c" <<'C'
+extern int ext_fn(void);
+extern int helper_fn(void);
+int strong_fn(void) { return ext_fn() + helper_fn(); }
+__attribute__((weak)) int weak_fn(void) { return 0; }
+C
+cat > "$work/helper.c" <<'C'
+extern int other_ext(void);
+int helper_fn(void) { return other_ext(); }
+C
+gcc $CFLAGS -c -o "$work/strong.o" "$work/strong.c"
+gcc $CFLAGS -c -o "$work/helper.o" "$work/helper.c"
+rm -f libstatic.a
+ar rcD libstatic.a "$work/strong.o" "$work/helper.o"
+cp "$work/strong.o" strong.o
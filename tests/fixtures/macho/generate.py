@@ -0,0 +1,108 @@
+#!/usr/bin/env python3
+# Regenerate the minimal Mach-O fixtures used by the macho unit tests. The
+# images hold only a header and the load commands bingraph reads.
+
+import struct
+from pathlib import Path
+
+MH_MAGIC_64 = 0xFEEDFACF
+FAT_MAGIC = 0xCAFEBABE
+CPU_TYPE_X86_64 = 0x01000007
+CPU_TYPE_ARM64 = 0x0100000C
+CPU_SUBTYPE_X86_64_ALL = 3
+CPU_SUBTYPE_ARM64_ALL = 0
+MH_EXECUTE = 0x2
+MH_DYLIB = 0x6
+
+LC_LOAD_DYLIB = 0xC
+LC_ID_DYLIB = 0xD
+LC_LOAD_WEAK_DYLIB = 0x80000018
+LC_RPATH = 0x8000001C
+LC_REEXPORT_DYLIB = 0x8000001F
+
+
+def pad(data, align=8):
+    return data + b"\0" * (-len(data) % align)
+
+
+def dylib_command(cmd, name):
+    body = pad(name.encode() + b"\0")
+    return struct.pack("<IIIIII", cmd, 24 + len(body), 24, 2, 0x10000, 0x10000) + body
+
+
+def rpath_command(path):
+    body = pad(path.encode() + b"\0", 4)
+    # Keep the following command 8 byte aligned.
+    body += b"\0" * (-(12 + len(body)) % 8)
+    return struct.pack("<III", LC_RPATH, 12 + len(body), 12) + body
+
+
+def image(cputype, cpusubtype, filetype, commands):
+    cmds = b"".join(commands)
+    header = struct.pack(
+        "<IiiIIIII", MH_MAGIC_64, cputype, cpusubtype, filetype, len(commands), len(cmds), 0, 0
+    )
+    return header + cmds
+
+
+def fat(slices, align=3):
+    header = struct.pack(">II", FAT_MAGIC, len(slices))
+    offset = len(header) + 20 * len(slices)
+    arches = b""
+    body = b""
+    for cputype, cpusubtype, data in slices:
+        offset += -offset % (1 << align)
+        body += b"\0" * (offset - len(header) - 20 * len(slices) - len(body))
+        arches += struct.pack(">iiIII", cputype, cpusubtype, offset, len(data), align)
+        body += data
+        offset += len(data)
+    return header + arches + body
+
+
+here = Path(__file__).parent
+
+(here / "thin.dylib").write_bytes(
+    image(
+        CPU_TYPE_X86_64,
+        CPU_SUBTYPE_X86_64_ALL,
+        MH_DYLIB,
+        [
+            dylib_command(LC_ID_DYLIB, "@rpath/libthin.dylib"),
+            rpath_command("@loader_path/../Frameworks"),
+            rpath_command("/opt/lib"),
+            dylib_command(LC_LOAD_DYLIB, "@rpath/libdep.dylib"),
+            dylib_command(LC_LOAD_WEAK_DYLIB, "@loader_path/libweak.dylib"),
+            dylib_command(LC_REEXPORT_DYLIB, "/usr/lib/libSystem.B.dylib"),
+        ],
+    )
+)
+
+(here / "fat").write_bytes(
+    fat(
+        [
+            (
+                CPU_TYPE_X86_64,
+                CPU_SUBTYPE_X86_64_ALL,
+                image(
+                    CPU_TYPE_X86_64,
+                    CPU_SUBTYPE_X86_64_ALL,
+                    MH_EXECUTE,
+                    [dylib_command(LC_LOAD_DYLIB, "@executable_path/../lib/libfoo.dylib")],
+                ),
+            ),
+            (
+                CPU_TYPE_ARM64,
+                CPU_SUBTYPE_ARM64_ALL,
+                image(
+                    CPU_TYPE_ARM64,
+                    CPU_SUBTYPE_ARM64_ALL,
+                    MH_EXECUTE,
+                    [
+                        dylib_command(LC_LOAD_DYLIB, "@executable_path/../lib/libfoo.dylib"),
+                        dylib_command(LC_LOAD_DYLIB, "/usr/lib/libobjc.A.dylib"),
+                    ],
+                ),
+            ),
+        ]
+    )
+)